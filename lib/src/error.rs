@@ -14,6 +14,12 @@ pub enum Error {
     #[error("Invalid config")]
     InvalidConfig,
 
+    #[error("Failed to read config")]
+    ReadConfig,
+
     #[error("Failed to fetch")]
     FetchError,
+
+    #[error("Rate limited")]
+    RateLimited,
 }
@@ -2,11 +2,6 @@
 
 set -e
 
-# Check if $1 is missing
-if [ -z "$CONFIG_PATH" ]; then
-    echo "Error: CONFIG_PATH environment variable is not set."
-    exit 1
-fi
-
-echo "entry.sh: Config $CONFIG_PATH"
-/app/grafana-shogun --config $CONFIG_PATH --log-level info
\ No newline at end of file
+# Config is read from $CONFIG_PATH when set, and can be overridden with SHOGUN__* environment variables
+echo "entry.sh: Config ${CONFIG_PATH:-<none>}"
+exec /app/grafana-shogun --log-level info "$@"
\ No newline at end of file
@@ -0,0 +1,660 @@
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use bech32::{FromBase32, Variant};
+use ethers::types::Address;
+use ethers::utils::to_checksum;
+
+use super::Chain;
+
+/// Length in bytes of a Solana public key
+const SOLANA_ADDRESS_LENGTH: usize = 32;
+
+/// Length in characters of a TON user-friendly address
+const TON_FRIENDLY_ADDRESS_LENGTH: usize = 48;
+
+/// Version byte of Tron addresses, making them start with `T` in base58
+const TRON_ADDRESS_PREFIX: u8 = 0x41;
+
+impl Chain {
+    /// Check that `address` is well formed for this chain, returning a
+    /// human-readable reason when it is not.
+    pub fn validate_address(&self, address: &str) -> Result<(), String> {
+        self.normalize_address(address).map(|_| ())
+    }
+
+    /// Canonical form of a well formed `address`, so that the different ways
+    /// of writing an address compare equal
+    pub fn normalize_address(&self, address: &str) -> Result<String, String> {
+        match self {
+            Chain::Evm(_) => validate_evm_address(address).map(|_| address.to_lowercase()),
+            Chain::Svm(_) => validate_solana_address(address).map(|_| address.to_string()),
+            Chain::Bitcoin(network) => normalize_bitcoin_address(network, address),
+            Chain::Cosmos(_) => normalize_cosmos_denom(address),
+            Chain::Aptos(_) => normalize_move_type("Aptos", address),
+            Chain::Sui(_) => normalize_move_type("Sui", address),
+            Chain::Ton(_) => normalize_ton_address(address),
+            Chain::Tron(_) => normalize_tron_address(address),
+        }
+    }
+}
+
+/// `0x`-prefixed 20 bytes hex address. Mixed-case addresses must be valid
+/// EIP-55 checksums, all lowercase or all uppercase ones are accepted as is.
+pub fn validate_evm_address(address: &str) -> Result<(), String> {
+    let hex = address
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("EVM address `{address}` must start with 0x"))?;
+
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "EVM address `{address}` must have 40 hexadecimal characters"
+        ));
+    }
+
+    let is_mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+
+    if is_mixed_case {
+        let checksummed = address
+            .parse::<Address>()
+            .map(|address| to_checksum(&address, None))
+            .map_err(|e| format!("EVM address `{address}` is invalid: {e}"))?;
+
+        if checksummed != address {
+            return Err(format!(
+                "EVM address `{address}` has an invalid EIP-55 checksum, expected `{checksummed}`"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Base58 encoded 32 bytes public key
+pub fn validate_solana_address(address: &str) -> Result<(), String> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|e| format!("Solana address `{address}` is not valid base58: {e}"))?;
+
+    if bytes.len() != SOLANA_ADDRESS_LENGTH {
+        return Err(format!(
+            "Solana address `{address}` must decode to {SOLANA_ADDRESS_LENGTH} bytes, got {}",
+            bytes.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Segwit (bech32, lowercased) or legacy (base58check) address of the network
+pub fn normalize_bitcoin_address(network: &str, address: &str) -> Result<String, String> {
+    let (hrp, versions) = match network {
+        "mainnet" => ("bc", [0x00, 0x05]),
+        "regtest" => ("bcrt", [0x6f, 0xc4]),
+        _ => ("tb", [0x6f, 0xc4]),
+    };
+
+    if address.to_lowercase().starts_with(&format!("{hrp}1")) {
+        let (_, data, variant) = bech32::decode(address)
+            .map_err(|e| format!("Bitcoin address `{address}` is not valid bech32: {e}"))?;
+
+        let (version, program) = data
+            .split_first()
+            .ok_or_else(|| format!("Bitcoin address `{address}` has no witness program"))?;
+        let version = version.to_u8();
+        let program = Vec::<u8>::from_base32(program)
+            .map_err(|e| format!("Bitcoin address `{address}` is not valid bech32: {e}"))?;
+
+        let expected_variant = match version {
+            0 => Variant::Bech32,
+            _ => Variant::Bech32m,
+        };
+
+        let valid_length = match version {
+            0 => program.len() == 20 || program.len() == 32,
+            _ => (2..=40).contains(&program.len()),
+        };
+
+        if version > 16 || variant != expected_variant || !valid_length {
+            return Err(format!(
+                "Bitcoin address `{address}` is not a valid segwit address"
+            ));
+        }
+
+        return Ok(address.to_lowercase());
+    }
+
+    let bytes = bs58::decode(address)
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| format!("Bitcoin address `{address}` is not valid base58check: {e}"))?;
+
+    if bytes.len() != 21 || !versions.contains(&bytes[0]) {
+        return Err(format!(
+            "Bitcoin address `{address}` is not a {network} address"
+        ));
+    }
+
+    Ok(address.to_string())
+}
+
+/// Native (`uatom`), IBC (`ibc/<hash>`, uppercased hash), token factory
+/// (`factory/<creator>/<subdenom>`) or CW20 contract (bech32, lowercased) denom
+pub fn normalize_cosmos_denom(denom: &str) -> Result<String, String> {
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "IBC denom `{denom}` must be `ibc/` followed by a 64 hexadecimal characters hash"
+            ));
+        }
+
+        return Ok(format!("ibc/{}", hash.to_uppercase()));
+    }
+
+    if let Some(rest) = denom.strip_prefix("factory/") {
+        let (creator, subdenom) = rest
+            .split_once('/')
+            .filter(|(_, subdenom)| !subdenom.is_empty())
+            .ok_or_else(|| {
+                format!("Token factory denom `{denom}` must be `factory/<creator>/<subdenom>`")
+            })?;
+
+        let creator = normalize_bech32(creator).map_err(|e| {
+            format!("Token factory denom `{denom}` has an invalid creator address: {e}")
+        })?;
+
+        return Ok(format!("factory/{creator}/{subdenom}"));
+    }
+
+    if let Ok(contract) = normalize_bech32(denom) {
+        return Ok(contract);
+    }
+
+    // Cosmos SDK denom format: [a-zA-Z][a-zA-Z0-9/:._-]{2,127}
+    let is_denom = (3..=128).contains(&denom.len())
+        && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+
+    if !is_denom {
+        return Err(format!(
+            "Cosmos denom `{denom}` must be a native, `ibc/`, `factory/` or CW20 contract denom"
+        ));
+    }
+
+    Ok(denom.to_string())
+}
+
+fn normalize_bech32(address: &str) -> Result<String, String> {
+    bech32::decode(address)
+        .map(|_| address.to_lowercase())
+        .map_err(|e| e.to_string())
+}
+
+/// Move address (`0x1`) or type tag (`0x1::aptos_coin::AptosCoin`), with its
+/// address lowercased and stripped of leading zeros
+pub fn normalize_move_type(chain: &str, type_tag: &str) -> Result<String, String> {
+    let (address, rest) = match type_tag.split_once("::") {
+        Some((address, rest)) => (address, Some(rest)),
+        None => (type_tag, None),
+    };
+
+    let address = normalize_move_address(address).ok_or_else(|| {
+        format!(
+            "{chain} address `{address}` must be 0x followed by up to 64 hexadecimal characters"
+        )
+    })?;
+
+    let Some(rest) = rest else {
+        return Ok(address);
+    };
+
+    let (module, name) = rest.split_once("::").unwrap_or((rest, ""));
+    let (base_name, generics) = match name.split_once('<') {
+        Some((base_name, generics)) => (base_name, Some(generics.strip_suffix('>'))),
+        None => (name, None),
+    };
+
+    if !is_move_identifier(module)
+        || !is_move_identifier(base_name)
+        || matches!(generics, Some(None))
+    {
+        return Err(format!(
+            "{chain} type `{type_tag}` must be `<address>::<module>::<name>`"
+        ));
+    }
+
+    let Some(Some(generics)) = generics else {
+        return Ok(format!("{address}::{module}::{base_name}"));
+    };
+
+    let type_arguments = split_type_arguments(generics)
+        .into_iter()
+        .map(|argument| normalize_move_type_argument(chain, argument))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(format!(
+        "{address}::{module}::{base_name}<{}>",
+        type_arguments.join(", ")
+    ))
+}
+
+/// Type argument of a generic Move type: a primitive, a vector or a type tag
+fn normalize_move_type_argument(chain: &str, argument: &str) -> Result<String, String> {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "u8", "u16", "u32", "u64", "u128", "u256", "address", "signer",
+    ];
+
+    if let Some(element) = argument
+        .strip_prefix("vector<")
+        .and_then(|vector| vector.strip_suffix('>'))
+    {
+        return Ok(format!(
+            "vector<{}>",
+            normalize_move_type_argument(chain, element.trim())?
+        ));
+    }
+
+    if PRIMITIVES.contains(&argument) {
+        return Ok(argument.to_string());
+    }
+
+    if argument.contains("::") {
+        return normalize_move_type(chain, argument);
+    }
+
+    Err(format!(
+        "{chain} type argument `{argument}` must be a primitive, a vector or a type"
+    ))
+}
+
+/// Comma separated type arguments, leaving the commas of nested generics
+fn split_type_arguments(generics: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, c) in generics.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(generics[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    arguments.push(generics[start..].trim());
+    arguments
+}
+
+fn normalize_move_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x")?;
+
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match hex.trim_start_matches('0') {
+        "" => Some(String::from("0x0")),
+        trimmed => Some(format!("0x{}", trimmed.to_lowercase())),
+    }
+}
+
+fn is_move_identifier(identifier: &str) -> bool {
+    identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Raw (`0:<hex>`) or user-friendly (base64) address, normalized to the raw
+/// form with a lowercase hash
+pub fn normalize_ton_address(address: &str) -> Result<String, String> {
+    if let Some((workchain, hash)) = address.split_once(':') {
+        let workchain = workchain
+            .parse::<i32>()
+            .map_err(|_| format!("TON address `{address}` has an invalid workchain"))?;
+
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "TON address `{address}` must have a 64 hexadecimal characters hash"
+            ));
+        }
+
+        return Ok(format!("{workchain}:{}", hash.to_lowercase()));
+    }
+
+    if address.len() != TON_FRIENDLY_ADDRESS_LENGTH {
+        return Err(format!(
+            "TON address `{address}` must be `<workchain>:<hash>` or a {TON_FRIENDLY_ADDRESS_LENGTH} characters user-friendly address"
+        ));
+    }
+
+    // Flags, workchain, 32 bytes hash and CRC16 checksum
+    let bytes = URL_SAFE
+        .decode(address.replace('+', "-").replace('/', "_"))
+        .map_err(|e| format!("TON address `{address}` is not valid base64: {e}"))?;
+
+    if bytes.len() != 36 {
+        return Err(format!("TON address `{address}` must decode to 36 bytes"));
+    }
+
+    let (data, checksum) = bytes.split_at(34);
+    if crc16(data).to_be_bytes() != checksum {
+        return Err(format!("TON address `{address}` has an invalid checksum"));
+    }
+
+    let hash: String = data[2..].iter().map(|byte| format!("{byte:02x}")).collect();
+
+    Ok(format!("{}:{hash}", data[1] as i8))
+}
+
+/// CRC16-XMODEM checksum of TON user-friendly addresses
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x1021,
+        })
+    })
+}
+
+/// Base58check (`T...`) or hex (`41...`) address, normalized to base58check
+pub fn normalize_tron_address(address: &str) -> Result<String, String> {
+    let is_hex = address.len() == 42 && address.chars().all(|c| c.is_ascii_hexdigit());
+
+    let bytes = match is_hex {
+        true => (0..address.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&address[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| format!("Tron address `{address}` is not valid hex: {e}"))?,
+        false => bs58::decode(address)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| format!("Tron address `{address}` is not valid base58check: {e}"))?,
+    };
+
+    if bytes.len() != 21 || bytes[0] != TRON_ADDRESS_PREFIX {
+        return Err(format!(
+            "Tron address `{address}` must be 21 bytes starting with 0x41"
+        ));
+    }
+
+    Ok(bs58::encode(bytes).with_check().into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::AssetId;
+    use bech32::ToBase32;
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    #[test]
+    fn evm_addresses_are_lowercased_when_their_checksum_is_valid() {
+        let chain = Chain::Evm(1);
+
+        for address in [
+            WETH,
+            &WETH.to_lowercase(),
+            &WETH.to_uppercase().replace("0X", "0x"),
+        ] {
+            assert_eq!(
+                chain.normalize_address(address),
+                Ok(WETH.to_lowercase()),
+                "{address}"
+            );
+        }
+
+        let bad_checksum = WETH.replace("aaA", "aaa");
+        let error = chain.normalize_address(&bad_checksum).unwrap_err();
+        assert!(error.contains(&format!("invalid EIP-55 checksum, expected `{WETH}`")));
+
+        assert!(chain.normalize_address(&WETH[2..]).is_err());
+        assert!(chain.normalize_address(&WETH[..40]).is_err());
+    }
+
+    #[test]
+    fn solana_addresses_are_kept_as_is() {
+        let chain = Chain::Svm(1);
+
+        assert_eq!(chain.normalize_address(USDC), Ok(USDC.to_string()));
+        // Base58 is case sensitive, and has no `0`
+        assert!(chain.normalize_address(&USDC.to_lowercase()).is_err());
+        assert!(chain.normalize_address(&USDC.replace('1', "0")).is_err());
+        assert!(chain.normalize_address(&USDC[..40]).is_err());
+    }
+
+    #[test]
+    fn tron_addresses_are_normalized_to_base58check() {
+        let chain = Chain::Tron(String::from("mainnet"));
+        let usdt = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
+
+        assert_eq!(chain.normalize_address(usdt), Ok(usdt.to_string()));
+        assert_eq!(
+            chain.normalize_address("41a614f803b6fd780986a42c78ec9c7f77e6ded13c"),
+            Ok(usdt.to_string())
+        );
+    }
+
+    #[test]
+    fn asset_ids_of_the_same_address_are_equal() {
+        let checksummed = AssetId::new(Chain::Evm(1), WETH).unwrap();
+        let lowercase = AssetId::new(Chain::Evm(1), &WETH.to_lowercase()).unwrap();
+
+        assert_eq!(checksummed, lowercase);
+        assert_eq!(checksummed.address, WETH.to_lowercase());
+        assert_ne!(checksummed, AssetId::new(Chain::Evm(10), WETH).unwrap());
+
+        assert!(AssetId::new(Chain::Evm(1), &WETH.replace("aaA", "aaa")).is_err());
+        assert!(AssetId::new(Chain::Svm(1), WETH).is_err());
+        assert_eq!(AssetId::new(Chain::Svm(1), USDC).unwrap().address, USDC);
+    }
+
+    #[test]
+    fn bitcoin_addresses_of_the_network_are_accepted() {
+        let mainnet = Chain::Bitcoin(String::from("mainnet"));
+
+        for address in [
+            // P2PKH and P2SH, base58check
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            // P2WPKH (bech32) and P2TR (bech32m)
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+        ] {
+            assert_eq!(
+                mainnet.normalize_address(address),
+                Ok(address.to_string()),
+                "{address}"
+            );
+        }
+
+        // Bech32 addresses may be written uppercase, e.g. in QR codes
+        assert_eq!(
+            mainnet.normalize_address("BC1QAR0SRRR7XFKVY5L643LYDNW9RE59GTZZWF5MDQ"),
+            Ok(String::from("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"))
+        );
+
+        let testnet = Chain::Bitcoin(String::from("testnet"));
+        assert!(testnet
+            .normalize_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .is_ok());
+    }
+
+    #[test]
+    fn bitcoin_addresses_with_a_bad_checksum_or_network_are_rejected() {
+        let mainnet = Chain::Bitcoin(String::from("mainnet"));
+
+        for address in [
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdr",
+            // Mixed case bech32
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mDQ",
+            // Testnet addresses
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+            "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn",
+        ] {
+            assert!(mainnet.normalize_address(address).is_err(), "{address}");
+        }
+    }
+
+    #[test]
+    fn cosmos_denoms_are_normalized() {
+        let chain = Chain::Cosmos(String::from("osmosis-1"));
+        let ibc = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+        let contract = bech32::encode("juno", [7u8; 32].to_base32(), Variant::Bech32).unwrap();
+
+        assert_eq!(chain.normalize_address("uosmo"), Ok(String::from("uosmo")));
+        assert_eq!(
+            chain.normalize_address(&ibc.to_lowercase()),
+            Ok(ibc.to_string())
+        );
+        assert_eq!(chain.normalize_address(ibc), Ok(ibc.to_string()));
+        assert_eq!(
+            chain.normalize_address(&contract.to_uppercase()),
+            Ok(contract.clone())
+        );
+        assert_eq!(
+            chain.normalize_address(&format!("factory/{}/ion", contract.to_uppercase())),
+            Ok(format!("factory/{contract}/ion"))
+        );
+    }
+
+    #[test]
+    fn malformed_cosmos_denoms_are_rejected() {
+        let chain = Chain::Cosmos(String::from("osmosis-1"));
+
+        for denom in [
+            "ibc/27394FB092D2",
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EBZ",
+            "factory/osmo1invalid/ion",
+            "factory/",
+            "1uosmo",
+            "ab",
+            "u osmo",
+        ] {
+            assert!(chain.normalize_address(denom).is_err(), "{denom}");
+        }
+    }
+
+    #[test]
+    fn move_type_tags_are_normalized() {
+        let aptos = Chain::Aptos(String::from("mainnet"));
+        let sui = Chain::Sui(String::from("mainnet"));
+
+        assert_eq!(
+            aptos.normalize_address("0x1::aptos_coin::AptosCoin"),
+            Ok(String::from("0x1::aptos_coin::AptosCoin"))
+        );
+        assert_eq!(
+            aptos.normalize_address(
+                "0x0000000000000000000000000000000000000000000000000000000000000001::aptos_coin::AptosCoin"
+            ),
+            Ok(String::from("0x1::aptos_coin::AptosCoin"))
+        );
+        assert_eq!(aptos.normalize_address("0x000"), Ok(String::from("0x0")));
+        assert_eq!(
+            sui.normalize_address("0xABC::lp::LP<0x2::sui::SUI>"),
+            Ok(String::from("0xabc::lp::LP<0x2::sui::SUI>"))
+        );
+        assert!(sui.normalize_address("0x2::coin::Coin<>").is_err());
+        assert!(sui.normalize_address("0x2::coin::Coin<sui>").is_err());
+
+        for type_tag in [
+            "1::aptos_coin::AptosCoin",
+            "0xZZ::aptos_coin::AptosCoin",
+            "0x1::aptos_coin",
+            "0x1::1coin::Coin",
+            "0x1::lp::LP<0x2::sui::SUI",
+        ] {
+            assert!(aptos.normalize_address(type_tag).is_err(), "{type_tag}");
+            assert!(sui.normalize_address(type_tag).is_err(), "{type_tag}");
+        }
+
+        let too_long = format!("0x{}::coin::Coin", "1".repeat(65));
+        assert!(sui.normalize_address(&too_long).is_err());
+    }
+
+    #[test]
+    fn type_arguments_of_generic_move_types_are_normalized() {
+        let sui = Chain::Sui(String::from("mainnet"));
+        let expected =
+            "0xabc::pool::Pool<0x2::sui::SUI, 0xdba3::usdc::USDC, vector<0x1::coin::Coin<u64>>>";
+
+        for type_tag in [
+            "0xABC::pool::Pool<0x2::sui::SUI, 0xdba3::usdc::USDC, vector<0x1::coin::Coin<u64>>>",
+            "0x0abc::pool::Pool<0x0002::sui::SUI,0xDBA3::usdc::USDC,vector< 0x01::coin::Coin<u64> >>",
+        ] {
+            assert_eq!(
+                sui.normalize_address(type_tag),
+                Ok(String::from(expected)),
+                "{type_tag}"
+            );
+        }
+
+        // Both spellings of the same type are the same asset
+        assert_eq!(
+            AssetId::new(sui.clone(), "0x5::lp::LP<0x0002::sui::SUI>").unwrap(),
+            AssetId::new(sui, "0x5::lp::LP<0x2::sui::SUI>").unwrap()
+        );
+    }
+
+    #[test]
+    fn ton_addresses_are_normalized_to_the_raw_form() {
+        let chain = Chain::Ton(String::from("mainnet"));
+        let usdt = "0:b113a994b5024a16719f69139328eb759596c38a25f59028b146fecdc3621dfe";
+
+        assert_eq!(
+            chain.normalize_address("EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs"),
+            Ok(usdt.to_string())
+        );
+        assert_eq!(
+            chain.normalize_address(&usdt.to_uppercase()),
+            Ok(usdt.to_string())
+        );
+
+        // Bad CRC16 checksum
+        let error = chain
+            .normalize_address("EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDt")
+            .unwrap_err();
+        assert!(error.contains("invalid checksum"), "{error}");
+
+        assert!(chain.normalize_address("x:b113a994b5024a16").is_err());
+        assert!(chain.normalize_address("0:b113a994").is_err());
+        assert!(chain
+            .normalize_address("EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7N")
+            .is_err());
+    }
+
+    #[test]
+    fn chains_round_trip_through_serde_as_kind_and_id() {
+        for (chain, text) in [
+            (Chain::Evm(42161), "evm:42161"),
+            (Chain::Svm(1), "svm:1"),
+            (Chain::Bitcoin(String::from("mainnet")), "bitcoin:mainnet"),
+            (
+                Chain::Cosmos(String::from("cosmoshub-4")),
+                "cosmos:cosmoshub-4",
+            ),
+            (Chain::Aptos(String::from("mainnet")), "aptos:mainnet"),
+            (Chain::Sui(String::from("testnet")), "sui:testnet"),
+            (Chain::Ton(String::from("mainnet")), "ton:mainnet"),
+            (Chain::Tron(String::from("mainnet")), "tron:mainnet"),
+        ] {
+            let json = serde_json::to_string(&chain).unwrap();
+            assert_eq!(json, format!("\"{text}\""));
+            assert_eq!(serde_json::from_str::<Chain>(&json).unwrap(), chain);
+        }
+
+        assert!(serde_json::from_str::<Chain>("\"bitcoin:liquid\"").is_err());
+        assert!(serde_json::from_str::<Chain>("\"near:mainnet\"").is_err());
+    }
+}
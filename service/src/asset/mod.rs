@@ -1,16 +1,208 @@
+use error_stack::Report;
+use lib::error::Error;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+pub mod address;
 pub mod price;
 
-#[derive(Debug, Clone)]
+/// Chain an asset lives on.
+///
+/// Represented in config as `<kind>:<id>`, e.g. `evm:1`, `svm:1`,
+/// `bitcoin:mainnet` or `cosmos:osmosis-1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Chain {
-    Svm(u64), // Solana
-    Evm(u64), // EVM L1/L2/L3
+    Svm(u64),        // Solana
+    Evm(u64),        // EVM L1/L2/L3
+    Bitcoin(String), // Bitcoin network: mainnet, testnet, signet or regtest
+    Cosmos(String),  // Cosmos SDK chain id, e.g. `cosmoshub-4`
+    Aptos(String),   // Aptos network, e.g. `mainnet`
+    Sui(String),     // Sui network, e.g. `mainnet`
+    Ton(String),     // TON network, e.g. `mainnet`
+    Tron(String),    // Tron network, e.g. `mainnet`
 }
 
-#[derive(Debug, Clone, buildstructor::Builder)]
+/// Networks a `bitcoin:<network>` chain can be
+const BITCOIN_NETWORKS: &[&str] = &["mainnet", "testnet", "signet", "regtest"];
+
+impl Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chain::Svm(id) => write!(f, "svm:{id}"),
+            Chain::Evm(id) => write!(f, "evm:{id}"),
+            Chain::Bitcoin(network) => write!(f, "bitcoin:{network}"),
+            Chain::Cosmos(chain_id) => write!(f, "cosmos:{chain_id}"),
+            Chain::Aptos(network) => write!(f, "aptos:{network}"),
+            Chain::Sui(network) => write!(f, "sui:{network}"),
+            Chain::Ton(network) => write!(f, "ton:{network}"),
+            Chain::Tron(network) => write!(f, "tron:{network}"),
+        }
+    }
+}
+
+impl FromStr for Chain {
+    type Err = Report<Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = s.split_once(':').ok_or_else(|| {
+            Report::new(Error::InvalidConfig)
+                .attach_printable(format!("Chain must be in `<kind>:<id>` form, got `{s}`"))
+        })?;
+
+        let numeric_id = || {
+            id.parse::<u64>().map_err(|_| {
+                Report::new(Error::InvalidConfig)
+                    .attach_printable(format!("Invalid chain id `{id}` in `{s}`"))
+            })
+        };
+
+        if id.is_empty() || id.contains(char::is_whitespace) {
+            return Err(Report::new(Error::InvalidConfig)
+                .attach_printable(format!("Invalid chain id `{id}` in `{s}`")));
+        }
+
+        match kind {
+            "svm" => Ok(Chain::Svm(numeric_id()?)),
+            "evm" => Ok(Chain::Evm(numeric_id()?)),
+            "bitcoin" if BITCOIN_NETWORKS.contains(&id) => Ok(Chain::Bitcoin(id.to_string())),
+            "bitcoin" => Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                "Unknown Bitcoin network `{id}` in `{s}`, expected one of {}",
+                BITCOIN_NETWORKS.join(", ")
+            ))),
+            "cosmos" => Ok(Chain::Cosmos(id.to_string())),
+            "aptos" => Ok(Chain::Aptos(id.to_string())),
+            "sui" => Ok(Chain::Sui(id.to_string())),
+            "ton" => Ok(Chain::Ton(id.to_string())),
+            "tron" => Ok(Chain::Tron(id.to_string())),
+            _ => Err(Report::new(Error::InvalidConfig)
+                .attach_printable(format!("Unknown chain kind `{kind}` in `{s}`"))),
+        }
+    }
+}
+
+impl TryFrom<String> for Chain {
+    type Error = Report<Error>;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Chain> for String {
+    fn from(chain: Chain) -> Self {
+        chain.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, buildstructor::Builder)]
 pub struct Asset {
     pub address: String,
     pub symbol: String,
     pub chain: Chain,
     pub name: Option<String>,
     pub decimals: u8,
+    #[serde(default)]
+    pub providers: AssetProviders,
+}
+
+impl Asset {
+    /// Canonical id of the asset. Addresses are validated with the config, so
+    /// an invalid one is only kept as is.
+    pub fn id(&self) -> AssetId {
+        AssetId::new(self.chain.clone(), &self.address).unwrap_or_else(|_| AssetId {
+            chain: self.chain.clone(),
+            address: self.address.clone(),
+        })
+    }
+}
+
+/// Identity of an asset: its chain and normalized address, so the same asset
+/// is found however its address is written (e.g. checksummed or lowercase EVM
+/// addresses). Assets are keyed by it everywhere.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId {
+    pub chain: Chain,
+    pub address: String,
+}
+
+impl AssetId {
+    /// Id of `address` on `chain`, failing when it is not a valid address of the chain
+    pub fn new(chain: Chain, address: &str) -> Result<Self, Report<Error>> {
+        let address = chain
+            .normalize_address(address)
+            .map_err(|reason| Report::new(Error::InvalidConfig).attach_printable(reason))?;
+
+        Ok(Self { chain, address })
+    }
+}
+
+impl Display for AssetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} on {}", self.address, self.chain)
+    }
+}
+
+/// Per-provider overrides for an asset, for when a provider does not know
+/// the asset by its plain chain and address.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetProviders {
+    /// DefiLlama coin identifier, e.g. `coingecko:ethereum`
+    pub defillama: Option<String>,
+    /// Pyth price feed id, e.g. `0xff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace`
+    pub pyth: Option<String>,
+    /// Chainlink aggregator proxy address, e.g. `0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419`
+    pub chainlink: Option<String>,
+    pub uniswap_v3: Option<UniswapV3Pool>,
+    pub uniswap_v2: Option<UniswapV2Pair>,
+    /// Binance spot symbol quoted in a USD stablecoin, e.g. `ETHUSDT`
+    pub binance: Option<String>,
+    /// Coinbase product id quoted in USD, e.g. `ETH-USD`
+    pub coinbase: Option<String>,
+    /// Ids of the asset by `[[providers.http]]` instance name
+    pub http: HashMap<String, String>,
+    /// Prices emitted by the mock provider
+    pub mock: Option<MockPrice>,
+    /// Providers by decreasing priority, overriding `aggregation.priority`
+    pub priority: Vec<String>,
+}
+
+/// Uniswap V3 pool pricing an asset against a quote asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniswapV3Pool {
+    /// Pool address
+    pub pool: String,
+    /// Address of the other pool token, which must be a tracked asset on the same chain
+    pub quote: String,
+    /// TWAP window in seconds, `providers.uniswap_v3.twap_window` when not set. `0` uses the spot price
+    pub twap_window: Option<u32>,
+}
+
+/// Uniswap V2-compatible pair pricing an asset against a quote asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UniswapV2Pair {
+    /// Pair address
+    pub pair: String,
+    /// Address of the other pair token, which must be a tracked asset on the same chain
+    pub quote: String,
+}
+
+/// Prices the mock provider emits for an asset, once per fetch interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MockPrice {
+    /// Always the same price
+    Fixed { price: Decimal },
+    /// Starts at `start`, then moves by a random step of at most `volatility` (e.g. `0.01` for 1%)
+    RandomWalk { start: Decimal, volatility: f64 },
+    /// Prices of a CSV (`timestamp,price` rows) or JSONL (`{"timestamp": ..., "price": ...}`
+    /// lines) file, in order. Timestamps are unix seconds, milliseconds or RFC 3339
+    Script {
+        path: String,
+        /// Start over at the end of the file instead of stopping
+        #[serde(default)]
+        repeat: bool,
+    },
 }
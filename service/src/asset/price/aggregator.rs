@@ -0,0 +1,916 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use error_stack::Result;
+use lib::error::Error;
+use opentelemetry::KeyValue;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::broadcast::{self as broadcast, Sender};
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tokio_stream::Stream;
+use tracing::{debug, error, info, warn};
+
+use super::price_provider::{AssetPriceEvent, AssetPriceProvider};
+use super::providers::broadcast_stream;
+use super::providers::health::{HealthState, ProviderHealth};
+use crate::asset::{Asset, AssetId};
+use crate::config::aggregation::{AggregationConfig, AggregationMethod, QuorumConfig};
+use crate::config::ConfigService;
+use crate::services::{ServiceFactory, ServiceProvider};
+use crate::telemetry;
+
+/// Price of an asset combined from the latest quote of every provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AggregatedPriceEvent {
+    pub asset: Asset,
+    pub price: Decimal,
+    pub method: AggregationMethod,
+    /// Providers whose quote contributed to the price
+    pub sources: Vec<AssetPriceProvider>,
+    /// Providers whose fresh quote was dropped as an outlier
+    pub rejected: Vec<AssetPriceProvider>,
+    pub aggregated_at: DateTime<Utc>,
+}
+
+/// The `priority` method started pricing an asset from another provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceSourceSwitchEvent {
+    pub asset: Asset,
+    pub from: AssetPriceProvider,
+    pub to: AssetPriceProvider,
+    /// Whether `to` has a higher priority than `from`, i.e. the asset is back
+    /// on a provider that recovered
+    pub failback: bool,
+    pub switched_at: DateTime<Utc>,
+}
+
+/// Too few providers of the quorum agreed on the price of an asset, so none
+/// was published
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoConsensusEvent {
+    pub asset: Asset,
+    /// Quorum providers with a fresh quote
+    pub reporting: Vec<AssetPriceProvider>,
+    /// Largest group of quorum providers within tolerance of each other
+    pub agreeing: Vec<AssetPriceProvider>,
+    /// Providers needed to agree, `quorum.min_providers`
+    pub required: usize,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+struct Quote {
+    event: AssetPriceEvent,
+    /// When the quote was received, as provider timestamps may lag (e.g. a
+    /// Chainlink round) or come from a recording
+    received_at: Instant,
+}
+
+#[derive(Clone, Debug, Default)]
+struct AssetQuotes {
+    /// Latest quote by provider
+    quotes: HashMap<AssetPriceProvider, Quote>,
+    /// Provider the `priority` method last priced the asset from
+    selected: Option<AssetPriceProvider>,
+    /// Last published price, and when
+    latest: Option<(AggregatedPriceEvent, Instant)>,
+}
+
+/// Keeps the latest quote per asset and provider, and publishes an aggregated
+/// price every time one of them changes.
+///
+/// Quotes older than `max_age` are ignored, and quotes deviating from the
+/// median by more than `max_deviation` are dropped as outliers before the
+/// remaining ones are combined. With a quorum, only the largest group of
+/// quorum providers agreeing within its tolerance is combined, counting quotes
+/// up to its own `max_age`.
+///
+/// The `priority` method instead skips quotes older than `failover_after` and
+/// quotes of unhealthy providers, without rejecting outliers. As a provider
+/// may fail without publishing anything, its selection is re-evaluated by
+/// `refresh` as well as on every quote.
+///
+/// Shared as a service, so providers can price an asset relative to the
+/// aggregated price of another one (e.g. a pool quoted in WETH).
+#[derive(Clone, Debug)]
+pub struct PriceAggregator {
+    /// Quotes by asset
+    assets: Arc<RwLock<HashMap<AssetId, AssetQuotes>>>,
+    sender: Sender<AggregatedPriceEvent>,
+    switch_sender: Sender<PriceSourceSwitchEvent>,
+    no_consensus_sender: Sender<NoConsensusEvent>,
+    /// Providers whose circuit is open or whose last call failed, by name
+    unhealthy: Arc<RwLock<HashSet<String>>>,
+    config: AggregationConfig,
+}
+
+impl PriceAggregator {
+    pub fn new(config: AggregationConfig) -> Self {
+        let (sender, _) = broadcast::channel::<AggregatedPriceEvent>(100);
+        let (switch_sender, _) = broadcast::channel::<PriceSourceSwitchEvent>(100);
+        let (no_consensus_sender, _) = broadcast::channel::<NoConsensusEvent>(100);
+
+        Self {
+            assets: Arc::new(RwLock::new(HashMap::new())),
+            sender,
+            switch_sender,
+            no_consensus_sender,
+            unhealthy: Arc::new(RwLock::new(HashSet::new())),
+            config,
+        }
+    }
+
+    /// Record a provider quote and broadcast the new aggregated price of its asset
+    pub async fn update(&self, event: AssetPriceEvent) {
+        let unhealthy = self.unhealthy.read().await.clone();
+        let mut assets = self.assets.write().await;
+        let asset_quotes = assets.entry(event.asset.id()).or_default();
+
+        let asset = event.asset.clone();
+        asset_quotes.quotes.insert(
+            event.provider.clone(),
+            Quote {
+                event,
+                received_at: Instant::now(),
+            },
+        );
+
+        if let Some((aggregated, _)) = self.reaggregate(&asset, asset_quotes, &unhealthy) {
+            self.publish(asset_quotes, aggregated);
+        }
+    }
+
+    /// Track which providers are unhealthy, for the `priority` method
+    pub async fn set_health(&self, health: &[ProviderHealth]) {
+        let unhealthy = health
+            .iter()
+            .filter(|health| health.state == HealthState::Open || health.consecutive_failures > 0)
+            .map(|health| health.provider.clone())
+            .collect();
+
+        *self.unhealthy.write().await = unhealthy;
+    }
+
+    /// Re-evaluate the provider of every asset priced with the `priority`
+    /// method, publishing the prices of assets switching to another one
+    pub async fn refresh(&self) {
+        if self.config.method != AggregationMethod::Priority {
+            return;
+        }
+
+        let unhealthy = self.unhealthy.read().await.clone();
+        let mut assets = self.assets.write().await;
+
+        for asset_quotes in assets.values_mut() {
+            let Some(asset) = asset_quotes
+                .quotes
+                .values()
+                .next()
+                .map(|quote| quote.event.asset.clone())
+            else {
+                continue;
+            };
+
+            if let Some((aggregated, true)) = self.reaggregate(&asset, asset_quotes, &unhealthy) {
+                self.publish(asset_quotes, aggregated);
+            }
+        }
+    }
+
+    /// Aggregate the fresh quotes of an asset, along with whether the
+    /// `priority` method switched it to another provider
+    fn reaggregate(
+        &self,
+        asset: &Asset,
+        asset_quotes: &mut AssetQuotes,
+        unhealthy: &HashSet<String>,
+    ) -> Option<(AggregatedPriceEvent, bool)> {
+        let max_age = Duration::from_secs(self.config.max_age);
+        asset_quotes
+            .quotes
+            .retain(|_, quote| quote.received_at.elapsed() <= max_age);
+
+        let is_priority = self.config.method == AggregationMethod::Priority;
+        let failover_after =
+            Duration::from_secs(self.config.failover_after.unwrap_or(self.config.max_age));
+        let quorum_max_age = self
+            .config
+            .quorum
+            .as_ref()
+            .and_then(|quorum| quorum.max_age)
+            .map(Duration::from_secs);
+
+        let mut fresh = asset_quotes
+            .quotes
+            .values()
+            .filter(|quote| {
+                quorum_max_age.is_none_or(|max_age| quote.received_at.elapsed() <= max_age)
+            })
+            .filter(|quote| {
+                !is_priority
+                    || (quote.received_at.elapsed() <= failover_after
+                        && !unhealthy.contains(&quote.event.provider.to_string()))
+            })
+            .map(|quote| &quote.event)
+            .collect::<Vec<_>>();
+        fresh.sort_by_key(|quote| quote.provider.to_string());
+
+        let aggregated = self.aggregate(asset, &fresh)?;
+
+        let mut switched = false;
+        if is_priority {
+            let selected = aggregated.sources.first().cloned();
+            let previous = std::mem::replace(&mut asset_quotes.selected, selected.clone());
+
+            if let (Some(from), Some(to)) = (previous, selected) {
+                if from != to {
+                    self.switch(asset.clone(), from, to);
+                    switched = true;
+                }
+            }
+        }
+
+        Some((aggregated, switched))
+    }
+
+    fn publish(&self, asset_quotes: &mut AssetQuotes, aggregated: AggregatedPriceEvent) {
+        asset_quotes.latest = Some((aggregated.clone(), Instant::now()));
+
+        // Sending only fails without subscribers
+        if let Err(e) = self.sender.send(aggregated) {
+            debug!("No subscriber for aggregated price: {}", e);
+        }
+    }
+
+    /// Last aggregated price of the asset, if published within `max_age`
+    pub async fn latest(
+        &self,
+        asset_id: &AssetId,
+        max_age: Duration,
+    ) -> Option<AggregatedPriceEvent> {
+        let assets = self.assets.read().await;
+        let (aggregated, published_at) = assets.get(asset_id)?.latest.as_ref()?;
+
+        (published_at.elapsed() <= max_age).then(|| aggregated.clone())
+    }
+
+    pub async fn remove(&self, asset_id: &AssetId) {
+        self.assets.write().await.remove(asset_id);
+    }
+
+    pub fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AggregatedPriceEvent> + Send>> {
+        broadcast_stream(&self.sender)
+    }
+
+    pub fn subscribe_switches(&self) -> Pin<Box<dyn Stream<Item = PriceSourceSwitchEvent> + Send>> {
+        broadcast_stream(&self.switch_sender)
+    }
+
+    pub fn subscribe_no_consensus(&self) -> Pin<Box<dyn Stream<Item = NoConsensusEvent> + Send>> {
+        broadcast_stream(&self.no_consensus_sender)
+    }
+
+    fn aggregate(
+        &self,
+        asset: &Asset,
+        quotes: &[&AssetPriceEvent],
+    ) -> Option<AggregatedPriceEvent> {
+        let (sources, rejected) = match (&self.config.quorum, self.config.method) {
+            (Some(quorum), _) => self.consensus(asset, quorum, quotes)?,
+            (None, AggregationMethod::Priority) => (quotes.to_vec(), vec![]),
+            (None, _) => self.reject_outliers(asset, quotes)?,
+        };
+
+        if sources.len() < self.config.min_sources {
+            debug!(
+                "Not enough quotes to price {}: {} of {}",
+                asset.symbol,
+                sources.len(),
+                self.config.min_sources
+            );
+            return None;
+        }
+
+        let (price, sources) = match self.config.method {
+            AggregationMethod::Median => (
+                median(sources.iter().map(|quote| quote.price).collect())?,
+                sources,
+            ),
+            AggregationMethod::WeightedMean => (self.weighted_mean(&sources)?, sources),
+            AggregationMethod::Priority => {
+                let primary = sources
+                    .iter()
+                    .copied()
+                    .min_by_key(|quote| self.rank(asset, &quote.provider))?;
+                (primary.price, vec![primary])
+            }
+        };
+
+        Some(AggregatedPriceEvent {
+            asset: asset.clone(),
+            price,
+            method: self.config.method,
+            sources: sources.iter().map(|quote| quote.provider.clone()).collect(),
+            rejected: rejected
+                .iter()
+                .map(|quote| quote.provider.clone())
+                .collect(),
+            aggregated_at: Utc::now(),
+        })
+    }
+
+    /// Split quotes between those within `max_deviation` of their median and outliers.
+    ///
+    /// When every quote is an outlier, e.g. two providers disagree, the quote of
+    /// the highest priority provider is kept, or every quote when none is listed.
+    fn reject_outliers<'a>(
+        &self,
+        asset: &Asset,
+        quotes: &[&'a AssetPriceEvent],
+    ) -> Option<(Vec<&'a AssetPriceEvent>, Vec<&'a AssetPriceEvent>)> {
+        let reference = median(quotes.iter().map(|quote| quote.price).collect())?;
+        let max_deviation = Decimal::from_f64(self.config.max_deviation)?;
+
+        let (mut sources, mut rejected): (Vec<_>, Vec<_>) =
+            quotes.iter().copied().partition(|quote| {
+                reference.is_zero()
+                    || ((quote.price - reference) / reference).abs() <= max_deviation
+            });
+
+        if sources.is_empty() {
+            let primary = rejected
+                .iter()
+                .copied()
+                .min_by_key(|quote| self.rank(asset, &quote.provider))
+                .filter(|quote| self.rank(asset, &quote.provider) < self.priority(asset).len());
+
+            (sources, rejected) = match primary {
+                Some(primary) => rejected
+                    .into_iter()
+                    .partition(|quote| quote.provider == primary.provider),
+                None => (rejected, vec![]),
+            };
+
+            warn!(
+                "No quote of {} is within {} of median {reference}, pricing it from {}",
+                asset.symbol,
+                self.config.max_deviation,
+                sources
+                    .iter()
+                    .map(|quote| quote.provider.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        if !rejected.is_empty() {
+            debug!(
+                "Dropped {} outlier quote(s) of {} around median {reference}",
+                rejected.len(),
+                asset.symbol
+            );
+        }
+
+        Some((sources, rejected))
+    }
+
+    /// Split the quotes of the quorum providers between the largest group
+    /// within `tolerance` of each other and the others, or broadcast a
+    /// `NoConsensusEvent` when that group is too small
+    fn consensus<'a>(
+        &self,
+        asset: &Asset,
+        quorum: &QuorumConfig,
+        quotes: &[&'a AssetPriceEvent],
+    ) -> Option<(Vec<&'a AssetPriceEvent>, Vec<&'a AssetPriceEvent>)> {
+        let tolerance = Decimal::from_f64(quorum.tolerance)?;
+
+        let mut members = quotes
+            .iter()
+            .copied()
+            .filter(|quote| {
+                quorum.providers.is_empty()
+                    || quorum.providers.contains(&quote.provider.to_string())
+            })
+            .collect::<Vec<_>>();
+        members.sort_by_key(|quote| quote.price);
+
+        // Sliding window over sorted prices, keeping the widest one in tolerance
+        let mut agreeing = 0..0;
+        let mut start = 0;
+        for end in 0..members.len() {
+            while members[end].price - members[start].price > members[start].price * tolerance {
+                start += 1;
+            }
+
+            if end + 1 - start > agreeing.len() {
+                agreeing = start..end + 1;
+            }
+        }
+
+        let providers = |quotes: &[&AssetPriceEvent]| {
+            let mut providers = quotes
+                .iter()
+                .map(|quote| quote.provider.clone())
+                .collect::<Vec<_>>();
+            providers.sort_by_key(|provider| provider.to_string());
+            providers
+        };
+
+        if agreeing.len() < quorum.min_providers {
+            warn!(
+                "No consensus on the price of {}: {} of {} required providers agree",
+                asset.symbol,
+                agreeing.len(),
+                quorum.min_providers
+            );
+
+            let no_consensus_counter = telemetry::get_meter_provider()
+                .meter("shogun")
+                .u64_counter("price_no_consensus")
+                .with_description("Number of times too few providers agreed on an asset price")
+                .build();
+
+            no_consensus_counter.add(
+                1,
+                &[
+                    KeyValue::new("asset", asset.symbol.clone()),
+                    KeyValue::new("chain", asset.chain.to_string()),
+                ],
+            );
+
+            let event = NoConsensusEvent {
+                asset: asset.clone(),
+                reporting: providers(&members),
+                agreeing: providers(&members[agreeing]),
+                required: quorum.min_providers,
+                checked_at: Utc::now(),
+            };
+
+            if let Err(e) = self.no_consensus_sender.send(event) {
+                debug!("No subscriber for price consensus: {}", e);
+            }
+
+            return None;
+        }
+
+        let mut rejected = [&members[..agreeing.start], &members[agreeing.end..]].concat();
+        rejected.sort_by_key(|quote| quote.provider.to_string());
+        let mut sources = members[agreeing].to_vec();
+        sources.sort_by_key(|quote| quote.provider.to_string());
+
+        Some((sources, rejected))
+    }
+
+    fn weighted_mean(&self, quotes: &[&AssetPriceEvent]) -> Option<Decimal> {
+        let (total, weights) =
+            quotes
+                .iter()
+                .try_fold((Decimal::ZERO, Decimal::ZERO), |(total, weights), quote| {
+                    let weight = self
+                        .config
+                        .weights
+                        .get(&quote.provider.to_string())
+                        .copied()
+                        .unwrap_or(1.0);
+
+                    let Some(weight) = Decimal::from_f64(weight) else {
+                        error!("Invalid aggregation weight of {}: {weight}", quote.provider);
+                        return None;
+                    };
+
+                    Some((total + quote.price * weight, weights + weight))
+                })?;
+
+        (!weights.is_zero()).then(|| total / weights)
+    }
+
+    /// Position of the provider in the priority list of the asset, its own or
+    /// its chain's or the default one. Unlisted providers rank last
+    fn rank(&self, asset: &Asset, provider: &AssetPriceProvider) -> usize {
+        let priority = self.priority(asset);
+
+        let name = provider.to_string();
+        priority
+            .iter()
+            .position(|other| *other == name)
+            .unwrap_or(priority.len())
+    }
+
+    /// Priority list of the asset, its own or its chain's or the default one
+    fn priority<'a>(&'a self, asset: &'a Asset) -> &'a [String] {
+        match asset.providers.priority.as_slice() {
+            [] => self
+                .config
+                .chain_priority
+                .get(&asset.chain)
+                .unwrap_or(&self.config.priority),
+            priority => priority,
+        }
+    }
+
+    fn switch(&self, asset: Asset, from: AssetPriceProvider, to: AssetPriceProvider) {
+        let failback = self.rank(&asset, &to) < self.rank(&asset, &from);
+
+        match failback {
+            true => info!("{} price failed back from {from} to {to}", asset.symbol),
+            false => warn!("{} price failed over from {from} to {to}", asset.symbol),
+        }
+
+        let switches_counter = telemetry::get_meter_provider()
+            .meter("shogun")
+            .u64_counter("price_source_switches")
+            .with_description("Number of times an asset price switched to another provider")
+            .build();
+
+        switches_counter.add(
+            1,
+            &[
+                KeyValue::new("asset", asset.symbol.clone()),
+                KeyValue::new("chain", asset.chain.to_string()),
+                KeyValue::new("from", from.to_string()),
+                KeyValue::new("to", to.to_string()),
+            ],
+        );
+
+        let event = PriceSourceSwitchEvent {
+            asset,
+            from,
+            to,
+            failback,
+            switched_at: Utc::now(),
+        };
+
+        if let Err(e) = self.switch_sender.send(event) {
+            debug!("No subscriber for price source switch: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for PriceAggregator {
+    async fn factory(services: ServiceProvider) -> Result<Self, Error> {
+        let config = services.get_service_unchecked::<ConfigService>().await;
+
+        Ok(PriceAggregator::new(config.aggregation.clone()))
+    }
+}
+
+/// Median of the prices, averaging the two middle ones of an even count
+fn median(mut prices: Vec<Decimal>) -> Option<Decimal> {
+    prices.sort();
+
+    let middle = prices.len() / 2;
+    match prices.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((prices[middle - 1] + prices[middle]) / Decimal::TWO),
+        _ => Some(prices[middle]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::price::price_provider::PriceMetadata;
+    use crate::asset::Chain;
+    use futures::{FutureExt, StreamExt};
+
+    fn weth() -> Asset {
+        Asset {
+            address: String::from("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
+            symbol: String::from("WETH"),
+            chain: Chain::Evm(1),
+            name: None,
+            decimals: 18,
+            providers: Default::default(),
+        }
+    }
+
+    fn quote(provider: AssetPriceProvider, price: i64) -> AssetPriceEvent {
+        AssetPriceEvent {
+            provider,
+            asset: weth(),
+            price: Decimal::from(price),
+            fetched_at: Utc::now(),
+            metadata: PriceMetadata::default(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn latest_is_only_returned_while_fresh() {
+        let aggregator = PriceAggregator::new(AggregationConfig::default());
+        let max_age = Duration::from_secs(30);
+        assert!(aggregator.latest(&weth().id(), max_age).await.is_none());
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2000))
+            .await;
+        let latest = aggregator.latest(&weth().id(), max_age).await.unwrap();
+        assert_eq!(latest.price, Decimal::from(2000));
+
+        tokio::time::advance(Duration::from_secs(31)).await;
+        assert!(aggregator.latest(&weth().id(), max_age).await.is_none());
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2100))
+            .await;
+        let latest = aggregator.latest(&weth().id(), max_age).await.unwrap();
+        assert_eq!(latest.price, Decimal::from(2100));
+
+        aggregator.remove(&weth().id()).await;
+        assert!(aggregator.latest(&weth().id(), max_age).await.is_none());
+    }
+
+    /// Price of `quotes` aggregated with `config`, and its sources and rejected providers
+    fn aggregate(
+        config: AggregationConfig,
+        quotes: &[AssetPriceEvent],
+    ) -> Option<(Decimal, Vec<AssetPriceProvider>, Vec<AssetPriceProvider>)> {
+        let quotes = quotes.iter().collect::<Vec<_>>();
+        let aggregated = PriceAggregator::new(config).aggregate(&weth(), &quotes)?;
+
+        Some((aggregated.price, aggregated.sources, aggregated.rejected))
+    }
+
+    #[test]
+    fn median_averages_the_two_middle_quotes() {
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 2010),
+            quote(AssetPriceProvider::CoinGecko, 2030),
+            quote(AssetPriceProvider::DeFiLlama, 2040),
+        ];
+
+        let (price, sources, rejected) = aggregate(AggregationConfig::default(), &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2020));
+        assert_eq!(sources.len(), 4);
+        assert!(rejected.is_empty());
+    }
+
+    #[test]
+    fn weighted_mean_defaults_unlisted_weights_to_one() {
+        let config = AggregationConfig {
+            method: AggregationMethod::WeightedMean,
+            weights: HashMap::from([(String::from("chainlink"), 3.0)]),
+            ..Default::default()
+        };
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 2040),
+        ];
+
+        let (price, ..) = aggregate(config, &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2030));
+    }
+
+    #[test]
+    fn outliers_are_rejected_around_the_median() {
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 2010),
+            quote(AssetPriceProvider::CoinGecko, 2500),
+        ];
+
+        let (price, sources, rejected) = aggregate(AggregationConfig::default(), &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2005));
+        assert_eq!(
+            sources,
+            vec![AssetPriceProvider::Pyth, AssetPriceProvider::Chainlink]
+        );
+        assert_eq!(rejected, vec![AssetPriceProvider::CoinGecko]);
+    }
+
+    #[test]
+    fn disagreeing_quotes_fall_back_to_the_priority_provider() {
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 3000),
+        ];
+
+        // Without priority, both quotes are kept
+        let (price, sources, rejected) = aggregate(AggregationConfig::default(), &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2500));
+        assert_eq!(sources.len(), 2);
+        assert!(rejected.is_empty());
+
+        let config = AggregationConfig {
+            priority: vec![String::from("chainlink"), String::from("pyth")],
+            ..Default::default()
+        };
+        let (price, sources, rejected) = aggregate(config, &quotes).unwrap();
+        assert_eq!(price, Decimal::from(3000));
+        assert_eq!(sources, vec![AssetPriceProvider::Chainlink]);
+        assert_eq!(rejected, vec![AssetPriceProvider::Pyth]);
+    }
+
+    fn priority() -> AggregationConfig {
+        AggregationConfig {
+            method: AggregationMethod::Priority,
+            priority: vec![String::from("pyth"), String::from("chainlink")],
+            failover_after: Some(10),
+            ..Default::default()
+        }
+    }
+
+    fn health(provider: &str, state: HealthState, consecutive_failures: u32) -> ProviderHealth {
+        ProviderHealth {
+            provider: provider.to_string(),
+            state,
+            consecutive_failures,
+            latency_ms: None,
+            last_success: None,
+            last_failure: None,
+            last_error: None,
+        }
+    }
+
+    async fn latest_price(aggregator: &PriceAggregator) -> Option<Decimal> {
+        let max_age = Duration::from_secs(60);
+        let latest = aggregator.latest(&weth().id(), max_age).await;
+        latest.map(|aggregated| aggregated.price)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn priority_fails_over_from_a_provider_that_stopped_publishing() {
+        let aggregator = PriceAggregator::new(priority());
+        let mut switches = aggregator.subscribe_switches();
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2000))
+            .await;
+        aggregator
+            .update(quote(AssetPriceProvider::Chainlink, 2100))
+            .await;
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2000)));
+
+        // Chainlink published since, but Pyth did not within `failover_after`
+        tokio::time::advance(Duration::from_secs(5)).await;
+        aggregator
+            .update(quote(AssetPriceProvider::Chainlink, 2100))
+            .await;
+        tokio::time::advance(Duration::from_secs(6)).await;
+        aggregator.refresh().await;
+
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2100)));
+        let switch = switches.next().now_or_never().flatten().unwrap();
+        assert_eq!(
+            (switch.from, switch.to, switch.failback),
+            (
+                AssetPriceProvider::Pyth,
+                AssetPriceProvider::Chainlink,
+                false
+            )
+        );
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2005))
+            .await;
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2005)));
+        assert!(switches.next().now_or_never().flatten().unwrap().failback);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn priority_skips_unhealthy_providers() {
+        let aggregator = PriceAggregator::new(priority());
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2000))
+            .await;
+        aggregator
+            .update(quote(AssetPriceProvider::Chainlink, 2100))
+            .await;
+
+        aggregator
+            .set_health(&[health("pyth", HealthState::Open, 5)])
+            .await;
+        aggregator.refresh().await;
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2100)));
+
+        aggregator
+            .set_health(&[health("pyth", HealthState::Healthy, 0)])
+            .await;
+        aggregator.refresh().await;
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2000)));
+
+        // A provider whose last call failed is skipped before its circuit opens
+        aggregator
+            .set_health(&[health("pyth", HealthState::Degraded, 1)])
+            .await;
+        aggregator.refresh().await;
+        assert_eq!(latest_price(&aggregator).await, Some(Decimal::from(2100)));
+    }
+
+    fn quorum(max_age: Option<u64>) -> AggregationConfig {
+        AggregationConfig {
+            quorum: Some(QuorumConfig {
+                min_providers: 2,
+                providers: vec![
+                    String::from("pyth"),
+                    String::from("chainlink"),
+                    String::from("coingecko"),
+                ],
+                tolerance: 0.01,
+                max_age,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quorum_combines_the_largest_agreeing_group() {
+        let quotes = [
+            quote(AssetPriceProvider::Chainlink, 2010),
+            quote(AssetPriceProvider::CoinGecko, 2100),
+            quote(AssetPriceProvider::DeFiLlama, 2000),
+            quote(AssetPriceProvider::Pyth, 2000),
+        ];
+
+        // DefiLlama is not part of the quorum, and CoinGecko is off by more than 1%
+        let (price, sources, rejected) = aggregate(quorum(None), &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2005));
+        assert_eq!(
+            sources,
+            vec![AssetPriceProvider::Chainlink, AssetPriceProvider::Pyth]
+        );
+        assert_eq!(rejected, vec![AssetPriceProvider::CoinGecko]);
+    }
+
+    #[test]
+    fn quorum_tolerance_bounds_the_spread_of_the_group() {
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 2020),
+            quote(AssetPriceProvider::CoinGecko, 2040),
+        ];
+
+        // Both neighbours of Chainlink are within 1% of it, but not of each other
+        let (_, sources, rejected) = aggregate(quorum(None), &quotes).unwrap();
+        assert_eq!(
+            sources,
+            vec![AssetPriceProvider::Chainlink, AssetPriceProvider::Pyth]
+        );
+        assert_eq!(rejected, vec![AssetPriceProvider::CoinGecko]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quorum_withholds_prices_without_consensus() {
+        let aggregator = PriceAggregator::new(quorum(Some(10)));
+        let mut no_consensus = aggregator.subscribe_no_consensus();
+
+        aggregator
+            .update(quote(AssetPriceProvider::Pyth, 2000))
+            .await;
+        aggregator
+            .update(quote(AssetPriceProvider::Chainlink, 2100))
+            .await;
+        assert_eq!(latest_price(&aggregator).await, None);
+
+        // One event per quote, the first one with Pyth alone
+        let _ = no_consensus.next().now_or_never().flatten().unwrap();
+        let event = no_consensus.next().now_or_never().flatten().unwrap();
+        assert_eq!(event.required, 2);
+        assert_eq!(event.agreeing.len(), 1);
+        assert_eq!(
+            event.reporting,
+            vec![AssetPriceProvider::Chainlink, AssetPriceProvider::Pyth]
+        );
+
+        aggregator
+            .update(quote(AssetPriceProvider::CoinGecko, 2005))
+            .await;
+        assert_eq!(
+            latest_price(&aggregator).await,
+            Some(Decimal::new(20025, 1))
+        );
+
+        // Past the quorum `max_age`, the Pyth quote no longer counts
+        tokio::time::advance(Duration::from_secs(11)).await;
+        aggregator
+            .update(quote(AssetPriceProvider::CoinGecko, 2005))
+            .await;
+        let event = no_consensus.next().now_or_never().flatten().unwrap();
+        assert_eq!(event.reporting, vec![AssetPriceProvider::CoinGecko]);
+    }
+
+    #[test]
+    fn priority_keeps_outliers() {
+        let quotes = [
+            quote(AssetPriceProvider::Pyth, 2000),
+            quote(AssetPriceProvider::Chainlink, 3000),
+            quote(AssetPriceProvider::CoinGecko, 3010),
+        ];
+
+        let (price, sources, rejected) = aggregate(priority(), &quotes).unwrap();
+        assert_eq!(price, Decimal::from(2000));
+        assert_eq!(sources, vec![AssetPriceProvider::Pyth]);
+        assert!(rejected.is_empty());
+    }
+}
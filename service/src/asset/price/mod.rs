@@ -1,33 +1,140 @@
+use aggregator::{AggregatedPriceEvent, NoConsensusEvent, PriceAggregator, PriceSourceSwitchEvent};
 use async_trait::async_trait;
 use error_stack::{Report, Result};
-use futures::{stream::select_all, Stream};
+use futures::{stream::select_all, Stream, StreamExt};
 use lib::error::Error;
 use price_provider::{AssetPriceEvent, PriceProvider};
-use providers::defillama::DefiLlamaProvider;
-use std::{pin::Pin, sync::OnceLock};
+use providers::health::ProviderHealth;
+use providers::{
+    binance::BinanceStreamProvider, chainlink::ChainlinkProvider, coinbase::CoinbaseStreamProvider,
+    coingecko::CoinGeckoProvider, defillama::DefiLlamaProvider, http::GenericHttpProvider,
+    jupiter::JupiterProvider, mock::MockProvider, pyth::PythProvider, replay::ReplayProvider,
+    uniswap_v2::UniswapV2Provider, uniswap_v3::UniswapV3Provider,
+};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tokio::{sync::watch, task::JoinHandle};
 use tracing::{info, warn};
 
-use crate::services::{ServiceFactory, ServiceProvider};
+use crate::{
+    config::{ConfigService, RecorderConfig},
+    services::{ServiceFactory, ServiceProvider},
+};
 
-use super::Asset;
+use super::{Asset, AssetId};
 
+pub mod aggregator;
 pub mod price_provider;
 pub mod providers;
+pub mod recorder;
 
 static SERVICE_INSTANCE: OnceLock<PriceService> = OnceLock::new();
 
+/// Interval the aggregator re-evaluates the provider of `priority` assets at
+const AGGREGATOR_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct PriceService {
-    providers: Vec<Box<dyn PriceProvider + Sync + Send>>,
+    providers: Vec<Arc<dyn PriceProvider + Sync + Send>>,
+    aggregator: Arc<PriceAggregator>,
+    recorder: Option<RecorderConfig>,
     is_running: bool,
 }
 
 impl PriceService {
     pub async fn new(services: ServiceProvider) -> Self {
-        let providers: Vec<Box<dyn PriceProvider + Sync + Send>> =
-            vec![Box::new(DefiLlamaProvider::new(services.clone()).await)];
+        let config = services.get_service_unchecked::<ConfigService>().await;
+        let aggregator = services.get_service_unchecked::<PriceAggregator>().await;
+
+        let mut providers: Vec<Arc<dyn PriceProvider + Sync + Send>> = Vec::new();
+
+        if config.providers.defillama.enabled {
+            let defillama = config.providers.defillama.clone();
+            providers.push(Arc::new(
+                DefiLlamaProvider::new(services.clone(), defillama).await,
+            ));
+        }
+
+        if let Some(coingecko) = config.providers.coingecko.clone() {
+            match CoinGeckoProvider::new(services.clone(), coingecko).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize CoinGecko provider: {e:?}"),
+            }
+        }
+
+        if let Some(binance) = config.providers.binance.clone() {
+            providers.push(Arc::new(
+                BinanceStreamProvider::new(services.clone(), binance).await,
+            ));
+        }
+
+        if let Some(coinbase) = config.providers.coinbase.clone() {
+            match CoinbaseStreamProvider::new(services.clone(), coinbase).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Coinbase provider: {e:?}"),
+            }
+        }
+
+        for http in config.providers.http.iter().cloned() {
+            let name = http.name.clone();
+            match GenericHttpProvider::new(services.clone(), http).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize HTTP provider {name}: {e:?}"),
+            }
+        }
+
+        if let Some(jupiter) = config.providers.jupiter.clone() {
+            match JupiterProvider::new(services.clone(), jupiter).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Jupiter provider: {e:?}"),
+            }
+        }
+
+        if let Some(mock) = config.providers.mock.clone() {
+            providers.push(Arc::new(MockProvider::new(services.clone(), mock).await));
+        }
+
+        if let Some(replay) = config.providers.replay.clone() {
+            providers.push(Arc::new(
+                ReplayProvider::new(services.clone(), replay).await,
+            ));
+        }
+
+        if let Some(pyth) = config.providers.pyth.clone() {
+            match PythProvider::new(services.clone(), pyth).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Pyth provider: {e:?}"),
+            }
+        }
+
+        if let Some(chainlink) = config.providers.chainlink.clone() {
+            match ChainlinkProvider::new(services.clone(), chainlink).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Chainlink provider: {e:?}"),
+            }
+        }
+
+        if let Some(uniswap_v3) = config.providers.uniswap_v3.clone() {
+            match UniswapV3Provider::new(services.clone(), uniswap_v3).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Uniswap V3 provider: {e:?}"),
+            }
+        }
+
+        if let Some(uniswap_v2) = config.providers.uniswap_v2.clone() {
+            match UniswapV2Provider::new(services.clone(), uniswap_v2).await {
+                Ok(provider) => providers.push(Arc::new(provider)),
+                Err(e) => warn!("Failed to initialize Uniswap V2 provider: {e:?}"),
+            }
+        }
 
         Self {
             providers,
+            aggregator,
+            recorder: config.tasks.recorder.clone(),
             is_running: false,
         }
     }
@@ -40,20 +147,123 @@ impl PriceService {
         }
     }
 
-    pub async fn remove_asset(&self, asset_address: String) {
+    pub async fn remove_asset(&self, asset_id: AssetId) {
+        self.aggregator.remove(&asset_id).await;
+
         for provider in self.providers.iter() {
-            if let Err(e) = provider.remove_asset(asset_address.clone()).await {
+            if let Err(e) = provider.remove_asset(asset_id.clone()).await {
                 warn!("Failed to remove asset from price provider: {e:?}");
             }
         }
     }
 
+    /// Update the fetch interval of every polling provider
+    pub async fn set_fetch_interval(&self, interval: u64) {
+        for provider in self.providers.iter() {
+            if let Err(e) = provider.set_fetch_interval(interval).await {
+                warn!("Failed to update price provider fetch interval: {e:?}");
+            }
+        }
+    }
+
+    /// Apply config changes to the running providers.
+    ///
+    /// Assets are diffed by id: removed assets are dropped from the
+    /// providers, modified ones are dropped then added back so providers
+    /// no longer pricing them forget them, new ones are added, and a new
+    /// fetcher interval is propagated. Subscribers are kept as providers are
+    /// never recreated.
+    pub fn watch_config(
+        self: Arc<Self>,
+        mut receiver: watch::Receiver<ConfigService>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut current = receiver.borrow_and_update().clone();
+
+            while receiver.changed().await.is_ok() {
+                let next = receiver.borrow_and_update().clone();
+                let diff = diff_assets(&current.assets, &next.assets);
+
+                for asset in diff.removed {
+                    info!(
+                        "Removing asset {} on {} after config reload",
+                        asset.address, asset.chain
+                    );
+                    self.remove_asset(asset.id()).await;
+                }
+
+                for asset in diff.modified {
+                    info!(
+                        "Updating asset {} on {} after config reload",
+                        asset.address, asset.chain
+                    );
+                    self.remove_asset(asset.id()).await;
+                    self.add_asset(asset).await;
+                }
+
+                for asset in diff.added {
+                    info!(
+                        "Adding asset {} on {} after config reload",
+                        asset.address, asset.chain
+                    );
+                    self.add_asset(asset).await;
+                }
+
+                if next.tasks.fetcher.interval != current.tasks.fetcher.interval {
+                    self.set_fetch_interval(next.tasks.fetcher.interval).await;
+                }
+
+                current = next;
+            }
+        })
+    }
+
     /// Start all price providers
     pub async fn start(&mut self) {
         if self.is_running {
             return;
         }
 
+        // Combine the quotes of every provider into one price per asset
+        let mut events = self.subscribe().await;
+        let aggregator = self.aggregator.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                aggregator.update(event).await;
+            }
+        });
+
+        // Let the priority method fail over from providers that became unhealthy
+        // or stopped publishing, which no new quote would reveal
+        let providers = self.providers.clone();
+        let aggregator = self.aggregator.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(AGGREGATOR_REFRESH_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let health = providers
+                    .iter()
+                    .map(|provider| provider.health())
+                    .collect::<Vec<_>>();
+                aggregator.set_health(&health).await;
+                aggregator.refresh().await;
+            }
+        });
+
+        // Replayed events are left out, so replaying a recording doesn't record it again
+        if let Some(recorder) = self.recorder.clone() {
+            let events = self
+                .providers
+                .iter()
+                .filter(|provider| provider.is_recorded())
+                .map(|provider| provider.subscribe())
+                .collect::<Vec<_>>();
+
+            recorder::spawn(recorder, Box::pin(select_all(events)));
+        }
+
         // Run price fetcher of every provider
         for provider in self.providers.iter() {
             provider.start();
@@ -63,6 +273,42 @@ impl PriceService {
         info!("Price providers started");
     }
 
+    /// Health of every provider
+    pub fn provider_health(&self) -> Vec<ProviderHealth> {
+        self.providers
+            .iter()
+            .map(|provider| provider.health())
+            .collect()
+    }
+
+    /// Latest price of an asset aggregated across providers, if published
+    /// within `max_age`
+    pub async fn latest_aggregated_price(
+        &self,
+        asset_id: &AssetId,
+        max_age: Duration,
+    ) -> Option<AggregatedPriceEvent> {
+        self.aggregator.latest(asset_id, max_age).await
+    }
+
+    /// Subscribe to the price of every asset aggregated across providers,
+    /// published whenever one of its quotes changes
+    pub fn subscribe_aggregated(&self) -> Pin<Box<dyn Stream<Item = AggregatedPriceEvent> + Send>> {
+        self.aggregator.subscribe()
+    }
+
+    /// Subscribe to the provider switches of assets priced with the `priority`
+    /// aggregation method
+    pub fn subscribe_switches(&self) -> Pin<Box<dyn Stream<Item = PriceSourceSwitchEvent> + Send>> {
+        self.aggregator.subscribe_switches()
+    }
+
+    /// Subscribe to the assets whose price was withheld because too few
+    /// providers of `aggregation.quorum` agreed on it
+    pub fn subscribe_no_consensus(&self) -> Pin<Box<dyn Stream<Item = NoConsensusEvent> + Send>> {
+        self.aggregator.subscribe_no_consensus()
+    }
+
     /// Subscribe to all price providers asset price events
     pub async fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>> {
         let mut streams = Vec::new();
@@ -77,6 +323,44 @@ impl PriceService {
     }
 }
 
+/// Assets that changed between two configs, matched by id
+#[derive(Debug, Default, PartialEq)]
+struct AssetDiff {
+    added: Vec<Asset>,
+    /// New declaration of assets declared differently
+    modified: Vec<Asset>,
+    removed: Vec<Asset>,
+}
+
+/// Compute which assets have been added, modified or removed
+fn diff_assets(previous: &[Asset], next: &[Asset]) -> AssetDiff {
+    let by_id = |assets: &[Asset]| {
+        assets
+            .iter()
+            .map(|asset| (asset.id(), asset.clone()))
+            .collect::<HashMap<_, _>>()
+    };
+    let (previous_ids, next_ids) = (by_id(previous), by_id(next));
+
+    let mut diff = AssetDiff::default();
+
+    for asset in next {
+        match previous_ids.get(&asset.id()) {
+            Some(previous) if previous == asset => {}
+            Some(_) => diff.modified.push(asset.clone()),
+            None => diff.added.push(asset.clone()),
+        }
+    }
+
+    diff.removed = previous
+        .iter()
+        .filter(|asset| !next_ids.contains_key(&asset.id()))
+        .cloned()
+        .collect();
+
+    diff
+}
+
 /// Get price service instance
 pub async fn get_instance(services: ServiceProvider) -> Result<&'static PriceService, Error> {
     if let Some(instance) = SERVICE_INSTANCE.get() {
@@ -96,3 +380,148 @@ impl ServiceFactory for PriceService {
         Ok(PriceService::new(services).await)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::Chain;
+    use futures::FutureExt;
+    use rust_decimal::Decimal;
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    /// Config pricing WETH with the mock provider at `price`, if any
+    fn config(weth_price: Option<u32>) -> ConfigService {
+        let mock = weth_price
+            .map(|price| format!("mock = {{ kind = \"fixed\", price = \"{price}\" }}"))
+            .unwrap_or_default();
+
+        format!(
+            r#"
+            [tasks.fetcher]
+            interval = 1
+
+            [environment]
+            name = "test"
+            otlp_grpc_endpoint = "http://localhost:4317"
+            otlp_http_endpoint = "http://localhost:4318"
+
+            [providers.defillama]
+            enabled = false
+
+            [providers.mock]
+
+            [[assets]]
+            address = "{WETH}"
+            symbol = "WETH"
+            chain = "evm:1"
+            decimals = 18
+            providers = {{ {mock} }}
+            "#
+        )
+        .parse()
+        .unwrap()
+    }
+
+    fn asset(address: &str, symbol: &str) -> Asset {
+        Asset {
+            address: address.to_string(),
+            symbol: symbol.to_string(),
+            chain: Chain::Evm(1),
+            name: None,
+            decimals: 18,
+            providers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn diff_assets_matches_assets_by_id() {
+        let weth = asset(WETH, "WETH");
+        let usdc = asset(USDC, "USDC");
+        let dai = asset("0x6B175474E89094C44Da98b954EedeAC495271d0F", "DAI");
+
+        let renamed_weth = Asset {
+            name: Some(String::from("Wrapped Ether")),
+            ..weth.clone()
+        };
+        let lowercase_usdc = asset(&USDC.to_lowercase(), "USDC");
+
+        let diff = diff_assets(
+            &[weth, usdc, dai.clone()],
+            &[renamed_weth.clone(), lowercase_usdc.clone()],
+        );
+
+        assert_eq!(
+            diff,
+            AssetDiff {
+                added: vec![],
+                modified: vec![renamed_weth, lowercase_usdc],
+                removed: vec![dai],
+            }
+        );
+
+        let diff = diff_assets(&[], &[asset(WETH, "WETH")]);
+        assert_eq!(diff.added, vec![asset(WETH, "WETH")]);
+        assert_eq!(
+            diff_assets(&[asset(WETH, "WETH")], &[asset(WETH, "WETH")]),
+            AssetDiff::default()
+        );
+    }
+
+    /// Start a price service on `config`, reloading it from the returned sender
+    async fn start(
+        config: ConfigService,
+    ) -> (
+        Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>>,
+        watch::Sender<ConfigService>,
+    ) {
+        let services = ServiceProvider::new();
+        services.add_service(config.clone()).await;
+
+        let mut price_service = PriceService::new(services).await;
+        for asset in config.assets.iter().cloned() {
+            price_service.add_asset(asset).await;
+        }
+
+        let events = price_service.subscribe().await;
+        price_service.start().await;
+
+        let (sender, receiver) = watch::channel(config);
+        Arc::new(price_service).watch_config(receiver);
+
+        (events, sender)
+    }
+
+    /// Let the reload apply, then drop the events published before it
+    async fn reload(
+        events: &mut Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>>,
+        sender: &watch::Sender<ConfigService>,
+        config: ConfigService,
+    ) {
+        sender.send_replace(config);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        while let Some(Some(_)) = events.next().now_or_never() {}
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reload_updates_modified_assets() {
+        let (mut events, sender) = start(config(Some(2000))).await;
+        assert_eq!(events.next().await.unwrap().price, Decimal::from(2000));
+
+        reload(&mut events, &sender, config(Some(3000))).await;
+        assert_eq!(events.next().await.unwrap().price, Decimal::from(3000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reload_stops_prices_of_a_dropped_provider_override() {
+        let (mut events, sender) = start(config(Some(2000))).await;
+        assert_eq!(events.next().await.unwrap().price, Decimal::from(2000));
+
+        reload(&mut events, &sender, config(None)).await;
+
+        let next = tokio::time::timeout(Duration::from_secs(5), events.next()).await;
+        assert!(next.is_err(), "WETH is still priced: {next:?}");
+    }
+}
@@ -1,30 +1,98 @@
-use crate::asset::Asset;
+use super::providers::health::ProviderHealth;
+use crate::asset::{Asset, AssetId};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use error_stack::Result;
 use lib::error::Error;
 use rust_decimal::Decimal;
-use std::pin::Pin;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, pin::Pin};
 use tokio::task::JoinHandle;
 use tokio_stream::Stream;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AssetPriceEvent {
     pub provider: AssetPriceProvider,
     pub asset: Asset,
     pub price: Decimal,
     pub fetched_at: DateTime<Utc>,
+    pub metadata: PriceMetadata,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// Extra details some providers publish along with the price
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceMetadata {
+    /// Confidence interval around the price, in the same unit as the price
+    pub confidence: Option<Decimal>,
+    /// Exponent the provider published the raw price and confidence with
+    pub exponent: Option<i32>,
+    /// USD value of the pool reserves backing an on-chain price
+    pub liquidity: Option<Decimal>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AssetPriceProvider {
     DeFiLlama,
+    CoinGecko,
+    Pyth,
+    Chainlink,
+    UniswapV3,
+    UniswapV2,
+    Jupiter,
+    Binance,
+    Coinbase,
+    /// `[[providers.http]]` instance, by name
+    Http(String),
+    Mock,
+}
+
+/// Name of the provider as used in config, e.g. `aggregation.weights`
+impl Display for AssetPriceProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetPriceProvider::DeFiLlama => write!(f, "defillama"),
+            AssetPriceProvider::CoinGecko => write!(f, "coingecko"),
+            AssetPriceProvider::Pyth => write!(f, "pyth"),
+            AssetPriceProvider::Chainlink => write!(f, "chainlink"),
+            AssetPriceProvider::UniswapV3 => write!(f, "uniswap_v3"),
+            AssetPriceProvider::UniswapV2 => write!(f, "uniswap_v2"),
+            AssetPriceProvider::Jupiter => write!(f, "jupiter"),
+            AssetPriceProvider::Binance => write!(f, "binance"),
+            AssetPriceProvider::Coinbase => write!(f, "coinbase"),
+            AssetPriceProvider::Http(name) => write!(f, "{name}"),
+            AssetPriceProvider::Mock => write!(f, "mock"),
+        }
+    }
 }
 
 #[async_trait]
 pub trait PriceProvider: Send + Sync {
     async fn add_asset(&self, asset: Asset) -> Result<(), Error>;
-    async fn remove_asset(&self, asset_address: String) -> Result<(), Error>;
+    async fn remove_asset(&self, asset_id: AssetId) -> Result<(), Error>;
     fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>>;
+
+    /// Spawn the task producing price events for `subscribe`.
+    ///
+    /// Polling providers fetch every asset on the fetch interval through
+    /// `polling::spawn`, while streaming providers keep a connection open and
+    /// reconnect on their own through `streaming::spawn`. Either way, assets
+    /// added or removed after `start` are picked up by the running task.
     fn start(&self) -> JoinHandle<Result<(), Error>>;
+
+    /// Health of the provider, from the outcome of its latest calls
+    fn health(&self) -> ProviderHealth;
+
+    /// Update the fetch interval (in seconds) of a running provider.
+    ///
+    /// Providers that don't poll can ignore it.
+    async fn set_fetch_interval(&self, _interval: u64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Whether `[tasks.recorder]` records the events of the provider, false for
+    /// providers playing back a recording
+    fn is_recorded(&self) -> bool {
+        true
+    }
 }
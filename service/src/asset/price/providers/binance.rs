@@ -0,0 +1,191 @@
+use super::streaming::{Streaming, StreamingProvider, StreamingState};
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::asset::Asset;
+use crate::config::providers::{BinanceConfig, BinanceStream};
+use crate::config::ConfigService;
+use crate::services::ServiceProvider;
+use async_trait::async_trait;
+use async_tungstenite::tokio::connect_async;
+use async_tungstenite::tungstenite::Message;
+use chrono::{TimeZone, Utc};
+use error_stack::{Report, Result, ResultExt};
+use futures_util::StreamExt;
+use lib::error::Error;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, str::FromStr};
+use tracing::{error, info};
+
+/// Pushes prices from Binance WebSocket ticker streams. Only assets with a
+/// `providers.binance` symbol are priced, quoted in a USD stablecoin.
+#[derive(Clone, Debug)]
+pub struct BinanceStreamProvider {
+    state: StreamingState,
+    config: BinanceConfig,
+}
+
+impl BinanceStreamProvider {
+    pub async fn new(services: ServiceProvider, config: BinanceConfig) -> Streaming<Self> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        Streaming::new(Self {
+            state: StreamingState::new(Self::NAME, &service_config),
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl StreamingProvider for BinanceStreamProvider {
+    const NAME: &'static str = "binance";
+
+    fn state(&self) -> &StreamingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.binance.is_none() {
+            info!(
+                "Asset has no Binance symbol, skipping in BinanceStreamProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    async fn stream(&self, assets: &[Asset]) -> Result<(), Error> {
+        // Tracked assets by uppercase Binance symbol
+        let symbols = assets
+            .iter()
+            .filter_map(|asset| {
+                let symbol = asset.providers.binance.as_ref()?;
+                Some((symbol.to_uppercase(), asset))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let stream_name = match self.config.stream {
+            BinanceStream::BookTicker => "bookTicker",
+            BinanceStream::MiniTicker => "miniTicker",
+        };
+
+        let streams = symbols
+            .keys()
+            .map(|symbol| format!("{}@{stream_name}", symbol.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let url = format!(
+            "{base_url}/stream?streams={streams}",
+            base_url = self.config.base_url.trim_end_matches('/')
+        );
+
+        let connecting_at = Instant::now();
+        let (mut socket, _) = connect_async(&url)
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Binance connection to {url} failed"))?;
+        self.state
+            .health
+            .record_success(connecting_at.elapsed(), None);
+
+        info!(
+            "Subscribed to Binance price stream for {} symbols",
+            symbols.len()
+        );
+
+        let throttle = Duration::from_millis(self.config.throttle_ms);
+        let mut last_sent: HashMap<String, Instant> = HashMap::new();
+
+        // Pings are answered by tungstenite while reading
+        while let Some(message) = socket.next().await {
+            let Message::Text(text) = message.change_context(Error::FetchError)? else {
+                continue;
+            };
+
+            let ticker = match serde_json::from_str::<StreamMessage>(&text) {
+                Ok(message) => message.data,
+                Err(e) => {
+                    error!("Failed to parse Binance stream message: {e}");
+                    continue;
+                }
+            };
+
+            let Some(asset) = symbols.get(&ticker.symbol) else {
+                error!(
+                    "Failed to find asset with Binance symbol: {}",
+                    ticker.symbol
+                );
+                continue;
+            };
+
+            if last_sent
+                .get(&ticker.symbol)
+                .is_some_and(|sent_at| sent_at.elapsed() < throttle)
+            {
+                continue;
+            }
+
+            let Some(price) = ticker.price(self.config.stream) else {
+                error!("Binance ticker of {} has no price", ticker.symbol);
+                continue;
+            };
+
+            let fetched_at = ticker
+                .event_time
+                .and_then(|timestamp| Utc.timestamp_millis_opt(timestamp).single())
+                .unwrap_or_else(Utc::now);
+
+            self.state.send(AssetPriceEvent {
+                provider: AssetPriceProvider::Binance,
+                asset: (*asset).clone(),
+                price,
+                fetched_at,
+                metadata: PriceMetadata::default(),
+            });
+
+            last_sent.insert(ticker.symbol, Instant::now());
+        }
+
+        Err(Report::new(Error::FetchError).attach_printable("Binance price stream ended"))
+    }
+}
+
+/// Message of a combined stream, wrapping the ticker of one symbol
+#[derive(Debug, Serialize, Deserialize)]
+struct StreamMessage {
+    stream: String,
+    data: Ticker,
+}
+
+/// Fields shared by `bookTicker` and `miniTicker` payloads
+#[derive(Debug, Serialize, Deserialize)]
+struct Ticker {
+    #[serde(rename = "s")]
+    symbol: String,
+    /// Event time in milliseconds, only sent by `miniTicker`
+    #[serde(rename = "E")]
+    event_time: Option<i64>,
+    #[serde(rename = "b")]
+    best_bid: Option<String>,
+    #[serde(rename = "a")]
+    best_ask: Option<String>,
+    #[serde(rename = "c")]
+    close: Option<String>,
+}
+
+impl Ticker {
+    fn price(&self, stream: BinanceStream) -> Option<Decimal> {
+        let parse = |value: &Option<String>| Decimal::from_str(value.as_deref()?).ok();
+
+        match stream {
+            BinanceStream::BookTicker => {
+                let mid = (parse(&self.best_bid)? + parse(&self.best_ask)?) / Decimal::TWO;
+                Some(mid.normalize())
+            }
+            BinanceStream::MiniTicker => parse(&self.close),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use super::evm::EvmClients;
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::config::providers::ChainlinkConfig;
+use crate::services::ServiceProvider;
+use crate::{
+    asset::{Asset, Chain},
+    config::ConfigService,
+};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use error_stack::{Report, Result, ResultExt};
+use ethers::contract::abigen;
+use ethers::types::{Address, I256};
+use futures::future::join_all;
+use lib::error::Error;
+use rust_decimal::Decimal;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{info, instrument};
+
+abigen!(
+    AggregatorV3Interface,
+    r#"[
+        function decimals() external view returns (uint8)
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
+
+/// Reads prices from Chainlink aggregator proxies over JSON-RPC. Only assets
+/// with a `providers.chainlink` aggregator on a chain with an `[[rpc]]` url
+/// are priced.
+#[derive(Clone, Debug)]
+pub struct ChainlinkProvider {
+    state: PollingState,
+    clients: EvmClients,
+    /// Limits the contract calls sent to the RPC urls
+    rate_limiter: RateLimiter,
+    /// Aggregator decimals never change, so they are only read once per chain
+    /// and aggregator
+    decimals: Arc<RwLock<HashMap<(Chain, Address), u8>>>,
+}
+
+impl ChainlinkProvider {
+    /// Aggregators are set per asset, so `ChainlinkConfig` has no setting yet
+    pub async fn new(services: ServiceProvider, _config: ChainlinkConfig) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+        let clients = EvmClients::new(&service_config.rpc)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            clients,
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            decimals: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching Chainlink prices for {:?} assets", assets.len());
+
+        let results = join_all(
+            assets
+                .iter()
+                .map(|asset| async move { Ok(vec![self.fetch_asset_price(asset).await?]) }),
+        )
+        .await;
+
+        merge_results(Self::NAME, results)
+    }
+
+    async fn fetch_asset_price(&self, asset: &Asset) -> Result<AssetPriceEvent, Error> {
+        let aggregator = asset.providers.chainlink.as_deref().unwrap_or_default();
+        let address = Address::from_str(aggregator)
+            .change_context(Error::InvalidConfig)
+            .attach_printable_lazy(|| format!("Invalid Chainlink aggregator {aggregator}"))?;
+
+        let client = self.clients.get(&asset.chain).ok_or_else(|| {
+            Report::new(Error::InvalidConfig)
+                .attach_printable(format!("No RPC url configured for {}", asset.chain))
+        })?;
+
+        let contract = AggregatorV3Interface::new(address, client);
+
+        let key = (asset.chain.clone(), address);
+        let cached_decimals = self.decimals.read().await.get(&key).copied();
+        let decimals = match cached_decimals {
+            Some(decimals) => decimals,
+            None => {
+                self.rate_limiter.acquire().await?;
+                let decimals = contract
+                    .decimals()
+                    .call()
+                    .await
+                    .change_context(Error::FetchError)
+                    .attach_printable_lazy(|| format!("Failed to read decimals of {aggregator}"))?;
+
+                self.decimals.write().await.insert(key, decimals);
+                decimals
+            }
+        };
+
+        self.rate_limiter.acquire().await?;
+        let (_, answer, _, updated_at, _) = contract
+            .latest_round_data()
+            .call()
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Failed to read latest round of {aggregator}"))?;
+
+        let price = answer_price(answer, decimals)
+            .attach_printable_lazy(|| format!("Invalid latest round of {aggregator}"))?;
+
+        let fetched_at = i64::try_from(updated_at.low_u64())
+            .ok()
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+            .unwrap_or_else(Utc::now);
+
+        Ok(AssetPriceEvent {
+            provider: AssetPriceProvider::Chainlink,
+            asset: asset.clone(),
+            price,
+            fetched_at,
+            metadata: PriceMetadata {
+                exponent: Some(-(decimals as i32)),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl PollingProvider for ChainlinkProvider {
+    const NAME: &'static str = "chainlink";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.chainlink.is_none() {
+            info!(
+                "Asset has no Chainlink aggregator, skipping in ChainlinkProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        if self.clients.get(&asset.chain).is_none() {
+            return Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                "No RPC url configured for {}, can't price {} with Chainlink",
+                asset.chain, asset.symbol
+            )));
+        }
+
+        Ok(true)
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+/// Price of a `latestRoundData` answer with the aggregator `decimals`, which
+/// must be positive and fit a `Decimal`
+fn answer_price(answer: I256, decimals: u8) -> Result<Decimal, Error> {
+    let answer = i128::try_from(answer)
+        .ok()
+        .filter(|answer| *answer > 0)
+        .ok_or_else(|| {
+            Report::new(Error::FetchError).attach_printable(format!("Invalid answer {answer}"))
+        })?;
+
+    Decimal::try_from_i128_with_scale(answer, decimals as u32)
+        .change_context(Error::FetchError)
+        .attach_printable_lazy(|| format!("Answer {answer} with {decimals} decimals out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answer_price_scales_by_the_decimals() {
+        let answer = I256::from(200_050_000_000_i64);
+
+        assert_eq!(
+            answer_price(answer, 8).unwrap(),
+            Decimal::from_str("2000.5").unwrap()
+        );
+        assert_eq!(
+            answer_price(answer, 18).unwrap(),
+            Decimal::from_str("0.00000020005").unwrap()
+        );
+        assert_eq!(
+            answer_price(answer, 0).unwrap(),
+            Decimal::from(200_050_000_000_i64)
+        );
+    }
+
+    #[test]
+    fn answer_price_rejects_invalid_answers() {
+        assert!(answer_price(I256::zero(), 8).is_err());
+        assert!(answer_price(I256::from(-1), 8).is_err());
+        assert!(answer_price(I256::MAX, 8).is_err());
+        assert!(answer_price(I256::from(1), 29).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use super::rate_limit::RateLimiter;
+use super::streaming::{Streaming, StreamingProvider, StreamingState};
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::asset::Asset;
+use crate::config::providers::CoinbaseConfig;
+use crate::config::ConfigService;
+use crate::services::ServiceProvider;
+use async_trait::async_trait;
+use async_tungstenite::tokio::connect_async;
+use async_tungstenite::tungstenite::Message;
+use chrono::{DateTime, Utc};
+use error_stack::{Report, Result, ResultExt};
+use futures::future::join_all;
+use futures_util::{SinkExt, StreamExt};
+use lib::error::Error;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, str::FromStr};
+use tracing::{error, info, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushes prices from the Coinbase Advanced Trade `ticker` channel. Only
+/// assets with a `providers.coinbase` product id are priced.
+///
+/// Messages are numbered per connection, so a gap in sequence numbers means
+/// ticks were missed and the latest prices are fetched from the REST API.
+#[derive(Clone, Debug)]
+pub struct CoinbaseStreamProvider {
+    state: StreamingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: CoinbaseConfig,
+}
+
+impl CoinbaseStreamProvider {
+    pub async fn new(
+        services: ServiceProvider,
+        config: CoinbaseConfig,
+    ) -> Result<Streaming<Self>, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .change_context(Error::Unknown)?;
+
+        Ok(Streaming::new(Self {
+            state: StreamingState::new(Self::NAME, &service_config),
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            client,
+            config,
+        }))
+    }
+
+    async fn stream_products(&self, products: &HashMap<String, Asset>) -> Result<(), Error> {
+        let url = &self.config.ws_url;
+
+        let connecting_at = Instant::now();
+        let (mut socket, _) = connect_async(url)
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Coinbase connection to {url} failed"))?;
+
+        // Heartbeats keep the connection open while products don't trade
+        for channel in ["ticker", "heartbeats"] {
+            let subscribe = json!({
+                "type": "subscribe",
+                "channel": channel,
+                "product_ids": products.keys().collect::<Vec<_>>(),
+            });
+
+            socket
+                .send(Message::Text(subscribe.to_string()))
+                .await
+                .change_context(Error::FetchError)
+                .attach_printable_lazy(|| format!("Failed to subscribe to Coinbase {channel}"))?;
+        }
+
+        self.state
+            .health
+            .record_success(connecting_at.elapsed(), None);
+        info!(
+            "Subscribed to Coinbase price stream for {} products",
+            products.len()
+        );
+
+        let mut last_sequence: Option<u64> = None;
+
+        while let Some(message) = socket.next().await {
+            let Message::Text(text) = message.change_context(Error::FetchError)? else {
+                continue;
+            };
+
+            let message = match serde_json::from_str::<StreamMessage>(&text) {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Failed to parse Coinbase stream message: {e}");
+                    continue;
+                }
+            };
+
+            if message.kind.as_deref() == Some("error") {
+                return Err(Report::new(Error::FetchError).attach_printable(format!(
+                    "Coinbase stream error: {}",
+                    message.message.unwrap_or_default()
+                )));
+            }
+
+            if let Some(sequence) = message.sequence_num {
+                if let Some(last) = last_sequence.filter(|last| sequence != last + 1) {
+                    warn!("Coinbase sequence gap from {last} to {sequence}, fetching a snapshot");
+                    self.send_snapshot(products).await;
+                }
+
+                last_sequence = Some(sequence);
+            }
+
+            if message.channel.as_deref() != Some("ticker") {
+                continue;
+            }
+
+            let fetched_at = message.timestamp.unwrap_or_else(Utc::now);
+
+            for ticker in message.events.into_iter().flat_map(|event| event.tickers) {
+                let Some(asset) = products.get(&ticker.product_id) else {
+                    error!(
+                        "Failed to find asset with Coinbase product id: {}",
+                        ticker.product_id
+                    );
+                    continue;
+                };
+
+                let Ok(price) = Decimal::from_str(&ticker.price) else {
+                    error!(
+                        "Invalid Coinbase price of {}: {}",
+                        ticker.product_id, ticker.price
+                    );
+                    continue;
+                };
+
+                self.state.send(price_event(asset, price, fetched_at));
+            }
+        }
+
+        Err(Report::new(Error::FetchError).attach_printable("Coinbase price stream ended"))
+    }
+
+    /// Broadcast the latest price of every product from the REST API
+    async fn send_snapshot(&self, products: &HashMap<String, Asset>) {
+        let snapshots = join_all(
+            products
+                .iter()
+                .map(|(product_id, asset)| self.fetch_product(product_id, asset)),
+        )
+        .await;
+
+        for snapshot in snapshots {
+            match snapshot {
+                Ok(event) => self.state.send(event),
+                Err(e) => error!("Failed to fetch Coinbase snapshot: {e:?}"),
+            }
+        }
+    }
+
+    async fn fetch_product(
+        &self,
+        product_id: &str,
+        asset: &Asset,
+    ) -> Result<AssetPriceEvent, Error> {
+        let url = format!(
+            "{rest_url}/api/v3/brokerage/market/products/{product_id}",
+            rest_url = self.config.rest_url.trim_end_matches('/')
+        );
+
+        let product = self
+            .rate_limiter
+            .send(self.client.get(&url))
+            .await
+            .attach_printable_lazy(|| format!("Coinbase request to {url} failed"))?
+            .json::<Product>()
+            .await
+            .change_context(Error::Deserialization)?;
+
+        let price = Decimal::from_str(&product.price)
+            .change_context(Error::Deserialization)
+            .attach_printable_lazy(|| format!("Invalid Coinbase price of {product_id}"))?;
+
+        Ok(price_event(asset, price, Utc::now()))
+    }
+}
+
+#[async_trait]
+impl StreamingProvider for CoinbaseStreamProvider {
+    const NAME: &'static str = "coinbase";
+
+    fn state(&self) -> &StreamingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.coinbase.is_none() {
+            info!(
+                "Asset has no Coinbase product id, skipping in CoinbaseStreamProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    async fn stream(&self, assets: &[Asset]) -> Result<(), Error> {
+        // Tracked assets by uppercase product id
+        let products = assets
+            .iter()
+            .filter_map(|asset| {
+                let product_id = asset.providers.coinbase.as_ref()?;
+                Some((product_id.to_uppercase(), asset.clone()))
+            })
+            .collect();
+
+        self.stream_products(&products).await
+    }
+}
+
+fn price_event(asset: &Asset, price: Decimal, fetched_at: DateTime<Utc>) -> AssetPriceEvent {
+    AssetPriceEvent {
+        provider: AssetPriceProvider::Coinbase,
+        asset: asset.clone(),
+        price,
+        fetched_at,
+        metadata: PriceMetadata::default(),
+    }
+}
+
+/// Message of any channel, or an error
+#[derive(Debug, Serialize, Deserialize)]
+struct StreamMessage {
+    channel: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    message: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+    sequence_num: Option<u64>,
+    #[serde(default)]
+    events: Vec<TickerEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TickerEvent {
+    #[serde(default)]
+    tickers: Vec<Ticker>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Ticker {
+    product_id: String,
+    price: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Product {
+    product_id: String,
+    price: String,
+}
@@ -0,0 +1,197 @@
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::asset::Chain;
+use crate::config::chains::ChainRegistry;
+use crate::config::providers::CoinGeckoConfig;
+use crate::services::ServiceProvider;
+use crate::{asset::Asset, config::ConfigService};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use error_stack::{Result, ResultExt};
+use futures::future::join_all;
+use lib::error::Error;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use tracing::{info, instrument, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prices tokens by contract address through CoinGecko's
+/// `/simple/token_price/{platform}` endpoint
+#[derive(Clone, Debug)]
+pub struct CoinGeckoProvider {
+    state: PollingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: CoinGeckoConfig,
+    chains: ChainRegistry,
+}
+
+impl CoinGeckoProvider {
+    pub async fn new(services: ServiceProvider, config: CoinGeckoConfig) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = &config.api_key {
+            let name = HeaderName::try_from(config.api_key_header.as_str())
+                .change_context(Error::InvalidConfig)
+                .attach_printable("Invalid CoinGecko API key header")?;
+            let value = HeaderValue::try_from(api_key.as_str())
+                .change_context(Error::InvalidConfig)
+                .attach_printable("Invalid CoinGecko API key")?;
+
+            headers.insert(name, value);
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .change_context(Error::Unknown)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            client,
+            config,
+            chains: ChainRegistry::new(&service_config.chains),
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching CoinGecko prices for {:?} assets", assets.len());
+
+        // Assets on chains without a platform are not added
+        let mut platforms: HashMap<(&str, Chain), Vec<Asset>> = HashMap::new();
+        for asset in assets {
+            if let Some(platform) = self.chains.coingecko(&asset.chain) {
+                platforms
+                    .entry((platform, asset.chain.clone()))
+                    .or_default()
+                    .push(asset);
+            }
+        }
+
+        let requests = platforms.iter().flat_map(|((platform, chain), assets)| {
+            assets
+                .chunks(self.config.batch_size)
+                .map(|batch| self.fetch_batch(platform, chain, batch))
+        });
+
+        merge_results(Self::NAME, join_all(requests).await)
+    }
+
+    async fn fetch_batch(
+        &self,
+        platform: &str,
+        chain: &Chain,
+        assets: &[Asset],
+    ) -> Result<Vec<AssetPriceEvent>, Error> {
+        // Addresses are requested as configured, as CoinGecko doesn't key every
+        // platform by the normalized form (e.g. user-friendly TON addresses),
+        // and responses are matched back by normalized address
+        let addresses: HashMap<String, &Asset> = assets
+            .iter()
+            .map(|asset| (asset.id().address, asset))
+            .collect();
+
+        let contract_addresses = assets
+            .iter()
+            .map(|asset| asset.address.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let url = format!(
+            "{base_url}/simple/token_price/{platform}",
+            base_url = self.config.base_url.trim_end_matches('/')
+        );
+
+        let request = self.client.get(&url).query(&[
+            ("contract_addresses", contract_addresses.as_str()),
+            ("vs_currencies", "usd"),
+            ("include_last_updated_at", "true"),
+        ]);
+
+        let prices = self
+            .rate_limiter
+            .send(request)
+            .await
+            .attach_printable_lazy(|| format!("CoinGecko request to {url} failed"))?
+            .json::<HashMap<String, TokenPrice>>()
+            .await
+            .change_context(Error::Deserialization)?;
+
+        let events = prices
+            .into_iter()
+            .filter_map(|(address, token_price)| {
+                // CoinGecko answers with lowercase EVM addresses
+                let address = chain.normalize_address(&address).unwrap_or(address);
+                let Some(asset) = addresses.get(&address) else {
+                    tracing::error!("Failed to find asset with address: {address}");
+                    return None;
+                };
+
+                let price = Decimal::from_f64(token_price.usd?)?;
+                let fetched_at = token_price
+                    .last_updated_at
+                    .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+                    .unwrap_or_else(Utc::now);
+
+                Some(AssetPriceEvent {
+                    provider: AssetPriceProvider::CoinGecko,
+                    asset: (*asset).clone(),
+                    price,
+                    fetched_at,
+                    metadata: PriceMetadata::default(),
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl PollingProvider for CoinGeckoProvider {
+    const NAME: &'static str = "coingecko";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if self.chains.coingecko(&asset.chain).is_none() {
+            warn!(
+                "CoinGecko platform unknown for chain {}, skipping asset {}",
+                asset.chain, asset.address
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenPrice {
+    usd: Option<f64>,
+    last_updated_at: Option<i64>,
+}
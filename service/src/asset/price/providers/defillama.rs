@@ -1,49 +1,50 @@
-use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceProvider};
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
 use crate::asset::Chain;
+use crate::config::chains::ChainRegistry;
+use crate::config::providers::DefiLlamaConfig;
 use crate::services::ServiceProvider;
-use crate::telemetry;
 use crate::{asset::Asset, config::ConfigService};
 use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
 use error_stack::{Result, ResultExt};
-use futures_util::StreamExt;
+use futures::stream::{self, StreamExt};
 use lib::error::Error;
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, pin::Pin, sync::Arc};
-use tokio::sync::broadcast::{self as broadcast, Sender};
-use tokio::sync::RwLock;
-use tokio_stream::{wrappers::BroadcastStream, Stream};
-use tracing::{info, info_span, instrument, Instrument};
-
-pub const DEFILLAMA_PRICE_FETCHER_URL: &str = "https://coins.llama.fi/prices/current";
+use std::collections::HashMap;
+use tracing::{info, instrument, warn};
 
 #[derive(Clone, Debug)]
 pub struct DefiLlamaProvider {
-    assets: Arc<RwLock<HashMap<String, Asset>>>,
-    sender: Sender<AssetPriceEvent>,
-    fetch_interval: u64,
+    state: PollingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: DefiLlamaConfig,
+    chains: ChainRegistry,
 }
 
 impl DefiLlamaProvider {
-    pub async fn new(services: ServiceProvider) -> Self {
-        let config = services.get_service_unchecked::<ConfigService>().await;
-
-        let interval = config.tasks.fetcher.interval;
-        // Create unbounded channel
-        let (sender, _) = broadcast::channel::<AssetPriceEvent>(100);
+    pub async fn new(services: ServiceProvider, config: DefiLlamaConfig) -> Self {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
 
         Self {
-            sender,
-            fetch_interval: interval,
-            assets: Arc::new(RwLock::new(HashMap::new())),
+            state: PollingState::new(Self::NAME, &service_config),
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            client: reqwest::Client::new(),
+            config,
+            chains: ChainRegistry::new(&service_config.chains),
         }
     }
 
-    #[instrument(name = "fetch_asset_prices")]
+    #[instrument(name = "fetch_asset_prices", skip(self))]
     pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
-        let assets = self.assets.read().await.clone();
+        let assets = self.state.assets().await;
 
         if assets.is_empty() {
             return Ok(vec![]);
@@ -51,52 +52,101 @@ impl DefiLlamaProvider {
 
         info!("Fetching DefiLlama prices for {:?} assets", assets.len());
 
-        let request_params: String = assets
-            .clone()
-            .into_iter()
-            .map(|(_, asset)| AssetIdentifier::from(asset).to_string())
-            .collect::<Vec<String>>()
-            .join(",");
+        // Map every DefiLlama identifier back to the asset it was requested for
+        let identifiers: HashMap<AssetIdentifier, Asset> = assets
+            .iter()
+            .filter_map(|asset| Some((AssetIdentifier::new(asset, &self.chains)?, asset.clone())))
+            .collect();
+
+        // Chains of the slugs, to normalize the addresses of the response
+        let slugs: HashMap<String, Chain> = identifiers
+            .iter()
+            .map(|(identifier, asset)| (identifier.slug.clone(), asset.chain.clone()))
+            .collect();
+
+        // Every coin is part of the URL path, so large lists are split to stay
+        // within URL length limits
+        let coins = assets
+            .iter()
+            .filter_map(|asset| coin(asset, &self.chains))
+            .collect::<Vec<String>>();
+        let chunks = coins
+            .chunks(self.config.batch_size)
+            .map(<[String]>::to_vec)
+            .collect::<Vec<_>>();
+        let (identifiers, slugs) = (&identifiers, &slugs);
+
+        let results = stream::iter(chunks)
+            .map(|chunk| async move { self.fetch_chunk(&chunk, identifiers, slugs).await })
+            .buffer_unordered(self.config.max_concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        merge_results(Self::NAME, results)
+    }
 
+    async fn fetch_chunk(
+        &self,
+        chunk: &[String],
+        identifiers: &HashMap<AssetIdentifier, Asset>,
+        slugs: &HashMap<String, Chain>,
+    ) -> Result<Vec<AssetPriceEvent>, Error> {
         let url = format!(
-            "{api_url}/{request_params}",
-            api_url = DEFILLAMA_PRICE_FETCHER_URL
+            "{base_url}/prices/current/{request_params}",
+            base_url = self.config.base_url.trim_end_matches('/'),
+            request_params = chunk.join(",")
         );
-        let response = reqwest::get(&url).await.change_context(Error::Unknown)?;
+
+        let response = self
+            .rate_limiter
+            .send(self.client.get(&url))
+            .await
+            .attach_printable_lazy(|| format!("DefiLlama request to {url} failed"))?;
 
         let feeds = response
             .json::<PriceResponse>()
             .await
-            .change_context(Error::Unknown)?;
+            .change_context(Error::Deserialization)?;
 
         let asset_price_events = feeds
             .coins
             .into_iter()
-            .filter_map(|(asset_id, coin_info)| {
-                let address = match asset_id.split(':').last() {
-                    Some(addr) => addr.to_string(),
+            .filter_map(|(coin, coin_info)| {
+                let identifier = AssetIdentifier::parse(&coin, slugs);
+                let asset = match identifiers.get(&identifier) {
+                    Some(asset) => asset.clone(),
                     None => {
-                        tracing::error!("Failed to parse asset id: {asset_id}");
+                        tracing::error!("Failed to find asset with identifier: {coin}");
                         return None;
                     }
                 };
 
-                let asset = match assets.get(&address) {
-                    Some(asset) => asset.clone(),
-                    None => {
-                        tracing::error!("Failed to find asset with address: {address}");
-                        return None;
-                    }
+                let Some(price) = coin_info
+                    .price
+                    .and_then(Decimal::from_f64)
+                    .filter(|price| price.is_sign_positive() && !price.is_zero())
+                else {
+                    warn!("Invalid DefiLlama price of {coin}: {:?}", coin_info.price);
+                    return None;
                 };
 
-                let price = Decimal::from_f64(coin_info.price).unwrap_or(Decimal::ZERO);
-                let fetched_at = Utc.timestamp_opt(coin_info.timestamp as i64, 0).unwrap(); // Should be safe to unwrap
+                let Some(fetched_at) = coin_info
+                    .timestamp
+                    .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+                else {
+                    warn!(
+                        "Invalid DefiLlama timestamp of {coin}: {:?}",
+                        coin_info.timestamp
+                    );
+                    return None;
+                };
 
                 Some(AssetPriceEvent {
                     provider: AssetPriceProvider::DeFiLlama,
                     asset,
                     price,
                     fetched_at,
+                    metadata: PriceMetadata::default(),
                 })
             })
             .collect::<Vec<AssetPriceEvent>>();
@@ -106,75 +156,27 @@ impl DefiLlamaProvider {
 }
 
 #[async_trait]
-impl PriceProvider for DefiLlamaProvider {
-    async fn add_asset(&self, asset: Asset) -> Result<(), Error> {
-        let mut assets = self.assets.write().await;
-        assets.insert(asset.address.clone(), asset.clone());
-        info!("Added asset to DefiLlamaProvider: {:?}", asset);
-        Ok(())
-    }
+impl PollingProvider for DefiLlamaProvider {
+    const NAME: &'static str = "defillama";
 
-    async fn remove_asset(&self, asset_address: String) -> Result<(), Error> {
-        let mut assets = self.assets.write().await;
-        assets.remove(&asset_address);
-        info!("Removed asset from DefiLlamaProvider: {:?}", asset_address);
-        Ok(())
+    fn state(&self) -> &PollingState {
+        &self.state
     }
 
-    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>> {
-        let stream = BroadcastStream::new(self.sender.subscribe()).filter_map(|event| async move {
-            match event {
-                Ok(event) => Some(event),
-                Err(_) => None,
-            }
-        });
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if AssetIdentifier::new(asset, &self.chains).is_none() {
+            info!(
+                "DefiLlama chain unknown for {}, skipping asset {} in DefiLlamaProvider",
+                asset.chain, asset.symbol
+            );
+            return Ok(false);
+        }
 
-        stream.boxed()
+        Ok(true)
     }
 
-    fn start(&self) -> tokio::task::JoinHandle<Result<(), Error>> {
-        let provider_clone = self.clone();
-        let sender = self.sender.clone();
-        let span = info_span!("price_provider", price_provider = "defillama").or_current();
-
-        tokio::spawn({
-            async move {
-                let mut interval = tokio::time::interval(std::time::Duration::from_secs(
-                    provider_clone.fetch_interval,
-                ));
-
-                loop {
-                    interval.tick().await;
-
-                    let times_fetched_counter = telemetry::get_meter_provider()
-                        .meter("shogun")
-                        .u64_counter("times_fetched_counter")
-                        .with_description("Number of times DefiLlama has been fetched")
-                        .build();
-
-                    times_fetched_counter.add(1, &[]);
-
-                    match provider_clone.fetch_asset_prices().await {
-                        Ok(price_events) => {
-                            info!("Fetched {} price events from DefiLlama", price_events.len());
-
-                            for event in price_events {
-                                if let Err(e) = sender.send(event) {
-                                    tracing::error!("Failed to broadcast price event: {}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to fetch asset prices from DefiLlama: {}", e);
-                            break;
-                        }
-                    }
-                }
-
-                Ok(())
-            }
-            .instrument(span)
-        })
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
     }
 }
 
@@ -185,28 +187,70 @@ struct PriceResponse {
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CoinInfo {
-    decimals: u8,
-    symbol: String,
-    price: f64,
-    timestamp: u64,
-    confidence: f64,
+    decimals: Option<u8>,
+    symbol: Option<String>,
+    price: Option<f64>,
+    timestamp: Option<i64>,
+    confidence: Option<f64>,
+}
+
+/// `<slug>:<address>` DefiLlama coin, with the address normalized on the
+/// chain of the slug, as DefiLlama may not answer with the address as
+/// requested (e.g. lowercase instead of checksummed EVM addresses)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AssetIdentifier {
+    slug: String,
+    address: String,
 }
 
-struct AssetIdentifier(String);
+impl AssetIdentifier {
+    /// Identifier of the coin requested for the asset. `None` when DefiLlama
+    /// doesn't know the chain.
+    fn new(asset: &Asset, chains: &ChainRegistry) -> Option<Self> {
+        let coin = coin(asset, chains)?;
+        let (slug, address) = coin.split_once(':').unwrap_or(("", &coin));
 
-impl ToString for AssetIdentifier {
-    fn to_string(&self) -> String {
-        self.0.clone()
+        Some(Self {
+            slug: slug.to_string(),
+            address: normalize(&asset.chain, address),
+        })
     }
-}
 
-impl From<Asset> for AssetIdentifier {
-    fn from(asset: Asset) -> Self {
-        let chain = match asset.chain {
-            Chain::Svm(_) => "solana",
-            Chain::Evm(_) => "ethereum", // TODO: Just added ethereum here for use case purposes, extra logic needed to handle other chains
+    /// Coin of a response, normalized on the chain of its slug in `slugs`
+    fn parse(coin: &str, slugs: &HashMap<String, Chain>) -> Self {
+        let (slug, address) = coin.split_once(':').unwrap_or(("", coin));
+        let address = match slugs.get(slug) {
+            Some(chain) => normalize(chain, address),
+            None => address.to_string(),
         };
 
-        Self(format!("{}:{}", chain, asset.address))
+        Self {
+            slug: slug.to_string(),
+            address,
+        }
+    }
+}
+
+/// Coin requested for the asset, its `providers.defillama` if any. The
+/// address is sent as configured, as DefiLlama doesn't key every chain by the
+/// normalized form (e.g. user-friendly `EQ…` TON addresses rather than raw
+/// `0:<hex>` ones). `None` when DefiLlama doesn't know the chain.
+fn coin(asset: &Asset, chains: &ChainRegistry) -> Option<String> {
+    if let Some(identifier) = &asset.providers.defillama {
+        return Some(identifier.clone());
     }
+
+    Some(format!(
+        "{}:{}",
+        chains.defillama(&asset.chain)?,
+        asset.address
+    ))
+}
+
+/// Normalized `address`, as is when it isn't an address of the chain, e.g.
+/// the CoinGecko id of a `coingecko:<id>` coin
+fn normalize(chain: &Chain, address: &str) -> String {
+    chain
+        .normalize_address(address)
+        .unwrap_or_else(|_| address.to_string())
 }
@@ -0,0 +1,105 @@
+use super::rate_limit::RateLimiter;
+use crate::asset::Chain;
+use crate::config::RpcConfig;
+use error_stack::{Result, ResultExt};
+use ethers::contract::abigen;
+use ethers::providers::{Http, Provider};
+use ethers::types::Address;
+use lib::error::Error;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::sync::RwLock;
+
+abigen!(
+    UniswapPool,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+    ]"#
+);
+
+/// JSON-RPC clients of the EVM chains declared in `[[rpc]]`
+#[derive(Clone, Debug, Default)]
+pub struct EvmClients {
+    clients: HashMap<u64, Arc<Provider<Http>>>,
+}
+
+impl EvmClients {
+    pub fn new(rpc: &[RpcConfig]) -> Result<Self, Error> {
+        let clients = rpc
+            .iter()
+            .map(|rpc| {
+                let provider = Provider::<Http>::try_from(rpc.url.as_str())
+                    .change_context(Error::InvalidConfig)
+                    .attach_printable_lazy(|| {
+                        format!("Invalid RPC url for chain {}: {}", rpc.chain_id, rpc.url)
+                    })?;
+
+                Ok((rpc.chain_id, Arc::new(provider)))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { clients })
+    }
+
+    /// Client of the chain, if it is an EVM chain with a configured RPC url
+    pub fn get(&self, chain: &Chain) -> Option<Arc<Provider<Http>>> {
+        match chain {
+            Chain::Evm(chain_id) => self.clients.get(chain_id).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// Token0 and token1 of pools, by chain and pool address
+type PoolTokenMap = HashMap<(Chain, Address), (Address, Address)>;
+
+/// Token0 and token1 of Uniswap pools and pairs. They never change, so they
+/// are only read once per chain and pool.
+#[derive(Clone, Debug, Default)]
+pub struct PoolTokens {
+    tokens: Arc<RwLock<PoolTokenMap>>,
+}
+
+impl PoolTokens {
+    /// Tokens of the pool at `pool` on `chain`, read through `client` when not cached
+    pub async fn get(
+        &self,
+        chain: &Chain,
+        pool: Address,
+        client: Arc<Provider<Http>>,
+        rate_limiter: &RateLimiter,
+    ) -> Result<(Address, Address), Error> {
+        let key = (chain.clone(), pool);
+        let cached_tokens = self.tokens.read().await.get(&key).copied();
+        if let Some(tokens) = cached_tokens {
+            return Ok(tokens);
+        }
+
+        let contract = UniswapPool::new(pool, client);
+
+        rate_limiter.acquire().await?;
+        let token0 = contract
+            .token_0()
+            .call()
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Failed to read token0 of {pool:?}"))?;
+        rate_limiter.acquire().await?;
+        let token1 = contract
+            .token_1()
+            .call()
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Failed to read token1 of {pool:?}"))?;
+
+        self.tokens.write().await.insert(key, (token0, token1));
+
+        Ok((token0, token1))
+    }
+}
+
+pub fn parse_address(address: &str) -> Result<Address, Error> {
+    Address::from_str(address)
+        .change_context(Error::InvalidConfig)
+        .attach_printable_lazy(|| format!("Invalid EVM address {address}"))
+}
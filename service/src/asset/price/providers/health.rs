@@ -0,0 +1,302 @@
+use crate::config::HealthConfig;
+use crate::telemetry;
+use chrono::{DateTime, Utc};
+use opentelemetry::KeyValue;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Health of a provider, doubling as the state of its circuit breaker
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Healthy,
+    /// Calls fail, are slow or return stale prices, but still go through
+    Degraded,
+    /// Too many consecutive failures, calls are skipped until `open_secs` elapsed
+    Open,
+    /// A probe call is allowed after the circuit was open, its outcome closes
+    /// or reopens the circuit
+    HalfOpen,
+}
+
+impl HealthState {
+    /// Value of the `provider_health_state` gauge
+    fn metric(&self) -> u64 {
+        match self {
+            HealthState::Healthy => 0,
+            HealthState::Degraded => 1,
+            HealthState::HalfOpen => 2,
+            HealthState::Open => 3,
+        }
+    }
+}
+
+/// Health of a provider as reported by `PriceProvider::health`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub state: HealthState,
+    pub consecutive_failures: u32,
+    /// Latency of the last successful call
+    pub latency_ms: Option<u64>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Tracks the outcome of the calls of a provider to compute its health and
+/// skip calls while its circuit is open
+#[derive(Clone, Debug)]
+pub struct HealthTracker {
+    state: Arc<Mutex<TrackerState>>,
+    config: HealthConfig,
+}
+
+#[derive(Debug)]
+struct TrackerState {
+    health: ProviderHealth,
+    /// When the circuit was last opened
+    opened_at: Option<Instant>,
+}
+
+impl HealthTracker {
+    pub fn new(provider: impl Into<String>, config: HealthConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TrackerState {
+                health: ProviderHealth {
+                    provider: provider.into(),
+                    state: HealthState::Healthy,
+                    consecutive_failures: 0,
+                    latency_ms: None,
+                    last_success: None,
+                    last_failure: None,
+                    last_error: None,
+                },
+                opened_at: None,
+            })),
+            config,
+        }
+    }
+
+    pub fn report(&self) -> ProviderHealth {
+        self.lock().health.clone()
+    }
+
+    /// Whether the provider may be called, moving an open circuit to half-open
+    /// once `open_secs` elapsed
+    pub fn allow_request(&self) -> bool {
+        self.open_remaining().is_none()
+    }
+
+    /// Time left before an open circuit lets a probe through, if it is open
+    pub fn open_remaining(&self) -> Option<Duration> {
+        let mut state = self.lock();
+
+        if state.health.state != HealthState::Open {
+            return None;
+        }
+
+        let elapsed = state.opened_at.map(|at| at.elapsed()).unwrap_or_default();
+        let open_for = Duration::from_secs(self.config.open_secs);
+
+        if elapsed < open_for {
+            return Some(open_for - elapsed);
+        }
+
+        transition(&mut state.health, HealthState::HalfOpen);
+        None
+    }
+
+    /// Record a successful call, returning prices published `oldest` at the
+    /// earliest if it returned any
+    pub fn record_success(&self, latency: Duration, oldest: Option<DateTime<Utc>>) {
+        let mut state = self.lock();
+        let health = &mut state.health;
+
+        let is_slow = latency > Duration::from_millis(self.config.slow_ms);
+        let is_stale = oldest.is_some_and(|oldest| {
+            (Utc::now() - oldest).num_seconds() > self.config.stale_after as i64
+        });
+
+        health.consecutive_failures = 0;
+        health.latency_ms = Some(latency.as_millis() as u64);
+        health.last_success = Some(Utc::now());
+
+        let state = match is_slow || is_stale {
+            true => HealthState::Degraded,
+            false => HealthState::Healthy,
+        };
+        transition(health, state);
+    }
+
+    pub fn record_failure(&self, error: &impl Display) {
+        let mut state = self.lock();
+        let health = &mut state.health;
+
+        health.consecutive_failures += 1;
+        health.last_failure = Some(Utc::now());
+        health.last_error = Some(error.to_string());
+
+        let next = match health.state {
+            HealthState::HalfOpen => HealthState::Open,
+            _ if health.consecutive_failures >= self.config.failure_threshold => HealthState::Open,
+            _ => HealthState::Degraded,
+        };
+        transition(health, next);
+
+        if next == HealthState::Open {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TrackerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Move to `state`, logging changes and recording the health gauge
+fn transition(health: &mut ProviderHealth, state: HealthState) {
+    let previous = std::mem::replace(&mut health.state, state);
+
+    if previous != state {
+        match state {
+            HealthState::Healthy | HealthState::HalfOpen => info!(
+                "{} provider health changed from {previous:?} to {state:?}",
+                health.provider
+            ),
+            HealthState::Degraded | HealthState::Open => warn!(
+                "{} provider health changed from {previous:?} to {state:?}",
+                health.provider
+            ),
+        }
+    }
+
+    let health_gauge = telemetry::get_meter_provider()
+        .meter("shogun")
+        .u64_gauge("provider_health_state")
+        .with_description("Health of a price provider: 0 healthy, 1 degraded, 2 half-open, 3 open")
+        .build();
+
+    health_gauge.record(
+        state.metric(),
+        &[KeyValue::new("provider", health.provider.clone())],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> HealthTracker {
+        HealthTracker::new(
+            "test",
+            HealthConfig {
+                failure_threshold: 3,
+                open_secs: 30,
+                slow_ms: 1000,
+                stale_after: 60,
+            },
+        )
+    }
+
+    /// Tracker whose circuit was just opened by `failure_threshold` failures
+    fn open_tracker() -> HealthTracker {
+        let health = tracker();
+        for _ in 0..3 {
+            health.record_failure(&"unreachable");
+        }
+        health
+    }
+
+    const FAST: Duration = Duration::from_millis(100);
+
+    #[tokio::test(start_paused = true)]
+    async fn degrades_on_slow_calls() {
+        let health = tracker();
+
+        health.record_success(Duration::from_secs(2), None);
+        assert_eq!(health.report().state, HealthState::Degraded);
+        assert_eq!(health.report().latency_ms, Some(2000));
+
+        health.record_success(FAST, None);
+        assert_eq!(health.report().state, HealthState::Healthy);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn degrades_on_stale_prices() {
+        let health = tracker();
+
+        health.record_success(FAST, Some(Utc::now() - chrono::Duration::seconds(120)));
+        assert_eq!(health.report().state, HealthState::Degraded);
+
+        health.record_success(FAST, Some(Utc::now() - chrono::Duration::seconds(10)));
+        assert_eq!(health.report().state, HealthState::Healthy);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_failure_threshold_failures() {
+        let health = tracker();
+
+        health.record_failure(&"unreachable");
+        health.record_failure(&"unreachable");
+        let report = health.report();
+        assert_eq!(report.state, HealthState::Degraded);
+        assert_eq!(report.consecutive_failures, 2);
+        assert_eq!(report.last_error.as_deref(), Some("unreachable"));
+        assert!(health.allow_request());
+
+        health.record_failure(&"unreachable");
+        assert_eq!(health.report().state, HealthState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn skips_calls_while_open() {
+        let health = open_tracker();
+
+        assert!(!health.allow_request());
+        assert_eq!(health.open_remaining(), Some(Duration::from_secs(30)));
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(!health.allow_request());
+        assert_eq!(health.open_remaining(), Some(Duration::from_secs(1)));
+        assert_eq!(health.report().state, HealthState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_opens_after_open_secs() {
+        let health = open_tracker();
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(health.allow_request());
+        assert_eq!(health.report().state, HealthState::HalfOpen);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successful_probe_closes_the_circuit() {
+        let health = open_tracker();
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(health.allow_request());
+
+        health.record_success(FAST, None);
+        let report = health.report();
+        assert_eq!(report.state, HealthState::Healthy);
+        assert_eq!(report.consecutive_failures, 0);
+        assert!(health.allow_request());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_probe_reopens_the_circuit() {
+        let health = open_tracker();
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(health.allow_request());
+
+        health.record_failure(&"still unreachable");
+        assert_eq!(health.report().state, HealthState::Open);
+        assert!(!health.allow_request());
+        assert_eq!(health.open_remaining(), Some(Duration::from_secs(30)));
+    }
+}
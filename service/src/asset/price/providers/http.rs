@@ -0,0 +1,257 @@
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::config::chains::ChainRegistry;
+use crate::config::providers::HttpProviderConfig;
+use crate::services::ServiceProvider;
+use crate::{asset::Asset, config::ConfigService};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use error_stack::{Result, ResultExt};
+use futures::future::join_all;
+use lib::error::Error;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::{str::FromStr, time::Duration};
+use tracing::{info, instrument, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timestamps above this are taken as milliseconds rather than seconds
+const MILLISECONDS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Prices assets from a JSON API described by a `[[providers.http]]` entry
+#[derive(Clone, Debug)]
+pub struct GenericHttpProvider {
+    state: PollingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: HttpProviderConfig,
+    chains: ChainRegistry,
+}
+
+impl GenericHttpProvider {
+    pub async fn new(services: ServiceProvider, config: HttpProviderConfig) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in config.headers.iter() {
+            let header_name = HeaderName::try_from(name.as_str())
+                .change_context(Error::InvalidConfig)
+                .attach_printable_lazy(|| format!("Invalid header name `{name}`"))?;
+            let header_value = HeaderValue::try_from(value.as_str())
+                .change_context(Error::InvalidConfig)
+                .attach_printable_lazy(|| format!("Invalid value of header `{name}`"))?;
+
+            headers.insert(header_name, header_value);
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .change_context(Error::Unknown)?;
+
+        Ok(Self {
+            state: PollingState::new(&config.name, &service_config),
+            rate_limiter: RateLimiter::new(
+                &config.name,
+                service_config.rate_limits.get(&config.name).cloned(),
+            ),
+            client,
+            config,
+            chains: ChainRegistry::new(&service_config.chains),
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self), fields(instance = %self.config.name))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!(
+            "Fetching {} prices for {:?} assets",
+            self.config.name,
+            assets.len()
+        );
+
+        let requests = assets
+            .chunks(self.config.batch_size)
+            .map(|batch| self.fetch_batch(batch));
+
+        merge_results(&self.config.name, join_all(requests).await)
+    }
+
+    async fn fetch_batch(&self, assets: &[Asset]) -> Result<Vec<AssetPriceEvent>, Error> {
+        let ids = assets
+            .iter()
+            .map(|asset| self.asset_id(asset))
+            .collect::<Vec<_>>();
+
+        let encoded_ids = ids
+            .iter()
+            .map(|id| encode_url_component(id))
+            .collect::<Vec<_>>();
+
+        let mut url = self
+            .config
+            .url
+            .replace("{ids}", &encoded_ids.join(&self.config.ids_separator));
+
+        if let ([asset], [id]) = (assets, ids.as_slice()) {
+            url = fill_template(&url, asset, id, &self.chain(asset), encode_url_component);
+        }
+
+        let response = self
+            .rate_limiter
+            .send(self.client.get(&url))
+            .await
+            .attach_printable_lazy(|| format!("{} request to {url} failed", self.config.name))?
+            .json::<Value>()
+            .await
+            .change_context(Error::Deserialization)?;
+
+        let fetched_at = Utc::now();
+
+        let events = assets
+            .iter()
+            .zip(ids.iter())
+            .filter_map(|(asset, id)| {
+                let pointer = fill_template(
+                    &self.config.price_pointer,
+                    asset,
+                    id,
+                    &self.chain(asset),
+                    escape_pointer,
+                );
+
+                let Some(price) = response.pointer(&pointer).and_then(parse_decimal) else {
+                    warn!(
+                        "{} has no price for {} at {pointer}",
+                        self.config.name, asset.symbol
+                    );
+                    return None;
+                };
+
+                let timestamp = self.config.timestamp_pointer.as_ref().and_then(|pointer| {
+                    let pointer =
+                        fill_template(pointer, asset, id, &self.chain(asset), escape_pointer);
+                    response.pointer(&pointer).and_then(parse_timestamp)
+                });
+
+                Some(AssetPriceEvent {
+                    provider: AssetPriceProvider::Http(self.config.name.clone()),
+                    asset: asset.clone(),
+                    price,
+                    fetched_at: timestamp.unwrap_or(fetched_at),
+                    metadata: PriceMetadata::default(),
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Id of the asset for this instance, its address unless overridden
+    fn asset_id(&self, asset: &Asset) -> String {
+        asset
+            .providers
+            .http
+            .get(&self.config.name)
+            .cloned()
+            .unwrap_or_else(|| asset.address.clone())
+    }
+
+    /// Name of the asset chain for this instance
+    fn chain(&self, asset: &Asset) -> String {
+        self.chains.http(&asset.chain, &self.config.name)
+    }
+}
+
+#[async_trait]
+impl PollingProvider for GenericHttpProvider {
+    const NAME: &'static str = "http";
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+/// Replace the asset placeholders of a template, escaping every value with `escape`
+fn fill_template(
+    template: &str,
+    asset: &Asset,
+    id: &str,
+    chain: &str,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    template
+        .replace("{id}", &escape(id))
+        .replace("{address}", &escape(&asset.address))
+        .replace("{symbol}", &escape(&asset.symbol))
+        .replace("{chain}", &escape(chain))
+}
+
+/// Percent-encode every byte but the RFC 3986 unreserved characters
+fn encode_url_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Escape a JSON pointer reference token
+fn escape_pointer(value: &str) -> String {
+    value.replace('~', "~0").replace('/', "~1")
+}
+
+/// Price as a JSON number or string
+pub(super) fn parse_decimal(value: &Value) -> Option<Decimal> {
+    let value = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(string) => string.clone(),
+        _ => return None,
+    };
+
+    Decimal::from_str(&value)
+        .or_else(|_| Decimal::from_scientific(&value))
+        .ok()
+}
+
+/// Timestamp in seconds or milliseconds, as a number or string, or RFC 3339
+pub(super) fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let timestamp = match value {
+        Value::Number(number) => number
+            .as_i64()
+            .or_else(|| number.as_f64().map(|n| n as i64))?,
+        Value::String(string) => match string.parse::<i64>() {
+            Ok(timestamp) => timestamp,
+            Err(_) => return DateTime::parse_from_rfc3339(string).ok().map(Into::into),
+        },
+        _ => return None,
+    };
+
+    match timestamp {
+        timestamp if timestamp > MILLISECONDS_THRESHOLD => {
+            Utc.timestamp_millis_opt(timestamp).single()
+        }
+        timestamp => Utc.timestamp_opt(timestamp, 0).single(),
+    }
+}
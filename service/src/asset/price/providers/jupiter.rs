@@ -0,0 +1,166 @@
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::asset::Chain;
+use crate::config::providers::JupiterConfig;
+use crate::services::ServiceProvider;
+use crate::{asset::Asset, config::ConfigService};
+use async_trait::async_trait;
+use chrono::Utc;
+use error_stack::{Result, ResultExt};
+use futures::future::join_all;
+use lib::error::Error;
+use reqwest::header::{HeaderMap, HeaderValue};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr, time::Duration};
+use tracing::{info, instrument, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Mainnet-beta cluster, the only one Jupiter prices
+const SOLANA_MAINNET: Chain = Chain::Svm(1);
+
+/// Prices Solana tokens by mint address through Jupiter's `/price/v2` endpoint.
+/// Jupiter only prices mainnet mints, so assets on other chains and clusters
+/// are skipped.
+#[derive(Clone, Debug)]
+pub struct JupiterProvider {
+    state: PollingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: JupiterConfig,
+}
+
+impl JupiterProvider {
+    pub async fn new(services: ServiceProvider, config: JupiterConfig) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = &config.api_key {
+            let value = HeaderValue::try_from(api_key.as_str())
+                .change_context(Error::InvalidConfig)
+                .attach_printable("Invalid Jupiter API key")?;
+
+            headers.insert("x-api-key", value);
+        }
+
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .change_context(Error::Unknown)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            client,
+            config,
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching Jupiter prices for {:?} assets", assets.len());
+
+        let requests = assets
+            .chunks(self.config.batch_size)
+            .map(|batch| self.fetch_batch(batch));
+
+        merge_results(Self::NAME, join_all(requests).await)
+    }
+
+    async fn fetch_batch(&self, assets: &[Asset]) -> Result<Vec<AssetPriceEvent>, Error> {
+        let ids = assets
+            .iter()
+            .map(|asset| asset.address.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let url = format!(
+            "{base_url}/price/v2",
+            base_url = self.config.base_url.trim_end_matches('/')
+        );
+
+        let prices = self
+            .rate_limiter
+            .send(self.client.get(&url).query(&[("ids", ids.as_str())]))
+            .await
+            .attach_printable_lazy(|| format!("Jupiter request to {url} failed"))?
+            .json::<PriceResponse>()
+            .await
+            .change_context(Error::Deserialization)?;
+
+        let fetched_at = Utc::now();
+
+        let events = assets
+            .iter()
+            .filter_map(|asset| {
+                // Jupiter answers with `null` for mints it can't price
+                let Some(Some(token_price)) = prices.data.get(&asset.address) else {
+                    warn!("Jupiter has no price for mint {}", asset.address);
+                    return None;
+                };
+
+                let price = Decimal::from_str(&token_price.price)
+                    .or_else(|_| Decimal::from_scientific(&token_price.price))
+                    .ok()?;
+
+                Some(AssetPriceEvent {
+                    provider: AssetPriceProvider::Jupiter,
+                    asset: asset.clone(),
+                    price,
+                    fetched_at,
+                    metadata: PriceMetadata::default(),
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl PollingProvider for JupiterProvider {
+    const NAME: &'static str = "jupiter";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.chain != SOLANA_MAINNET {
+            info!(
+                "Asset is not on Solana mainnet, skipping in JupiterProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PriceResponse {
+    data: HashMap<String, Option<TokenPrice>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenPrice {
+    id: String,
+    price: String,
+}
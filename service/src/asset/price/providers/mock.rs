@@ -0,0 +1,217 @@
+use super::http::{parse_decimal, parse_timestamp};
+use super::polling::{PollingProvider, PollingState};
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::asset::MockPrice;
+use crate::config::providers::MockConfig;
+use crate::services::ServiceProvider;
+use crate::{
+    asset::{Asset, AssetId},
+    config::ConfigService,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// Emits prices from config instead of a real source, to run offline or get
+/// deterministic prices. Only assets with a `providers.mock` price are priced.
+#[derive(Clone, Debug)]
+pub struct MockProvider {
+    state: PollingState,
+    /// Ordered by address so random walks don't depend on insertion order
+    assets: Arc<Mutex<BTreeMap<AssetId, MockAsset>>>,
+    rng: Arc<Mutex<StdRng>>,
+}
+
+#[derive(Debug)]
+struct MockAsset {
+    asset: Asset,
+    source: MockSource,
+}
+
+/// Current state of a `MockPrice`
+#[derive(Debug)]
+enum MockSource {
+    Fixed(Decimal),
+    RandomWalk {
+        price: Decimal,
+        volatility: f64,
+    },
+    Script {
+        points: Vec<ScriptPoint>,
+        next: usize,
+        repeat: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ScriptPoint {
+    timestamp: DateTime<Utc>,
+    price: Decimal,
+}
+
+impl MockProvider {
+    pub async fn new(services: ServiceProvider, config: MockConfig) -> Self {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            assets: Arc::new(Mutex::new(BTreeMap::new())),
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(config.seed))),
+        }
+    }
+
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let mut assets = self.assets.lock().await;
+        let mut rng = self.rng.lock().await;
+        let now = Utc::now();
+
+        let events = assets
+            .values_mut()
+            .filter_map(|mock| {
+                let (price, fetched_at) = match &mut mock.source {
+                    MockSource::Fixed(price) => (*price, now),
+                    MockSource::RandomWalk { price, volatility } => {
+                        let step = Decimal::from_f64(rng.gen_range(-*volatility..=*volatility))?;
+                        *price *= Decimal::ONE + step;
+                        (*price, now)
+                    }
+                    MockSource::Script {
+                        points,
+                        next,
+                        repeat,
+                    } => {
+                        if *next >= points.len() && *repeat {
+                            *next = 0;
+                        }
+
+                        let point = points.get(*next)?;
+                        *next += 1;
+                        (point.price, point.timestamp)
+                    }
+                };
+
+                Some(AssetPriceEvent {
+                    provider: AssetPriceProvider::Mock,
+                    asset: mock.asset.clone(),
+                    price,
+                    fetched_at,
+                    metadata: PriceMetadata::default(),
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl PollingProvider for MockProvider {
+    const NAME: &'static str = "mock";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        let Some(mock_price) = &asset.providers.mock else {
+            info!(
+                "Asset has no mock price, skipping in MockProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        };
+
+        let source = match mock_price {
+            MockPrice::Fixed { price } => MockSource::Fixed(*price),
+            MockPrice::RandomWalk { start, volatility } => MockSource::RandomWalk {
+                price: *start,
+                volatility: *volatility,
+            },
+            MockPrice::Script { path, repeat } => MockSource::Script {
+                points: read_script(path).await?,
+                next: 0,
+                repeat: *repeat,
+            },
+        };
+
+        self.assets.lock().await.insert(
+            asset.id(),
+            MockAsset {
+                asset: asset.clone(),
+                source,
+            },
+        );
+        Ok(true)
+    }
+
+    async fn forget(&self, asset_id: &AssetId) {
+        self.assets.lock().await.remove(asset_id);
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+/// Read the points of a CSV or JSONL script, depending on the file extension
+async fn read_script(path: &str) -> Result<Vec<ScriptPoint>, Error> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .change_context(Error::ReadConfig)
+        .attach_printable_lazy(|| format!("Failed to read mock script {path}"))?;
+
+    let is_jsonl = path.ends_with(".jsonl") || path.ends_with(".ndjson");
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| {
+            let (timestamp, price) = match is_jsonl {
+                true => match serde_json::from_str::<Value>(line) {
+                    Ok(value) => (value["timestamp"].clone(), value["price"].clone()),
+                    Err(e) => return Some(Err(invalid_line(path, index, e))),
+                },
+                false => {
+                    let (timestamp, price) = line.split_once(',').unwrap_or((line, ""));
+                    (
+                        Value::String(timestamp.trim().to_string()),
+                        Value::String(price.trim().to_string()),
+                    )
+                }
+            };
+
+            let point = parse_timestamp(&timestamp)
+                .zip(parse_decimal(&price))
+                .map(|(timestamp, price)| ScriptPoint { timestamp, price });
+
+            match (point, index) {
+                (Some(point), _) => Some(Ok(point)),
+                // Skip a CSV header
+                (None, 0) if !is_jsonl => None,
+                (None, _) => Some(Err(invalid_line(
+                    path,
+                    index,
+                    "expected a timestamp and a price",
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn invalid_line(path: &str, index: usize, reason: impl std::fmt::Display) -> Report<Error> {
+    Report::new(Error::InvalidConfig).attach_printable(format!(
+        "Invalid mock script line {} of {path}: {reason}",
+        index + 1
+    ))
+}
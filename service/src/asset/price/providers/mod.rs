@@ -1 +1,32 @@
+use futures_util::StreamExt;
+use std::pin::Pin;
+use tokio::sync::broadcast::Sender;
+use tokio_stream::{wrappers::BroadcastStream, Stream};
+
+pub mod binance;
+pub mod chainlink;
+pub mod coinbase;
+pub mod coingecko;
 pub mod defillama;
+pub mod evm;
+pub mod health;
+pub mod http;
+pub mod jupiter;
+pub mod mock;
+pub mod polling;
+pub mod pyth;
+pub mod rate_limit;
+pub mod replay;
+pub mod retry;
+pub mod streaming;
+pub mod uniswap_v2;
+pub mod uniswap_v3;
+
+/// Stream of the events broadcast through `sender`, skipping lagged ones
+pub(crate) fn broadcast_stream<T: Clone + Send + 'static>(
+    sender: &Sender<T>,
+) -> Pin<Box<dyn Stream<Item = T> + Send>> {
+    BroadcastStream::new(sender.subscribe())
+        .filter_map(|event| async move { event.ok() })
+        .boxed()
+}
@@ -0,0 +1,302 @@
+use super::broadcast_stream;
+use super::health::{HealthTracker, ProviderHealth};
+use super::retry::{backoff, is_transient};
+use crate::asset::price::price_provider::{AssetPriceEvent, PriceProvider};
+use crate::asset::{Asset, AssetId};
+use crate::config::{ConfigService, RetryPolicy};
+use crate::telemetry;
+use async_trait::async_trait;
+use error_stack::{Report, Result};
+use lib::error::Error;
+use opentelemetry::KeyValue;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self as broadcast, Sender};
+use tokio::sync::{watch, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{Interval, MissedTickBehavior};
+use tokio_stream::Stream;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// Price provider fetching every asset price on a fixed interval.
+///
+/// Polling providers implement `PriceProvider` through their `PollingState`,
+/// and only decide which assets they price and how to fetch them.
+#[async_trait]
+pub trait PollingProvider: Clone + Send + Sync + 'static {
+    /// Provider name used in logs, spans and metrics
+    const NAME: &'static str;
+
+    /// Name of this provider instance, `NAME` unless several instances of the
+    /// provider can run side by side
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn state(&self) -> &PollingState;
+
+    /// Check the provider can price an asset, and prepare pricing it, before it
+    /// is tracked. `Ok(false)` skips an asset the provider doesn't price, while
+    /// errors are for assets it should price but can't
+    async fn prepare(&self, _asset: &Asset) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    /// Drop what `prepare` kept about an asset that is no longer tracked
+    async fn forget(&self, _asset_id: &AssetId) {}
+
+    /// Spawn the task producing prices, polling `fetch` unless overridden
+    fn run(&self) -> JoinHandle<Result<(), Error>> {
+        let state = self.state();
+        spawn(
+            self.clone(),
+            state.sender.clone(),
+            &state.fetch_interval,
+            &state.retry,
+            &state.health,
+        )
+    }
+
+    /// Fetch the current price of every tracked asset
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error>;
+}
+
+/// Tracked assets, price channel and polling settings of a polling provider
+#[derive(Clone, Debug)]
+pub struct PollingState {
+    pub assets: Arc<RwLock<HashMap<AssetId, Asset>>>,
+    pub sender: Sender<AssetPriceEvent>,
+    pub fetch_interval: Arc<watch::Sender<u64>>,
+    pub retry: RetryPolicy,
+    pub health: HealthTracker,
+    /// Notified when the tracked assets change, for providers that can also stream
+    pub assets_changed: Arc<Notify>,
+}
+
+impl PollingState {
+    /// State of the provider, or `[[providers.http]]` instance, named `name`
+    pub fn new(name: &str, config: &ConfigService) -> Self {
+        let (sender, _) = broadcast::channel::<AssetPriceEvent>(100);
+
+        Self {
+            assets: Arc::new(RwLock::new(HashMap::new())),
+            sender,
+            fetch_interval: Arc::new(watch::Sender::new(config.tasks.fetcher.interval)),
+            retry: config.tasks.fetcher.retry.policy(name),
+            health: HealthTracker::new(name, config.health.clone()),
+            assets_changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Snapshot of the tracked assets
+    pub async fn assets(&self) -> Vec<Asset> {
+        self.assets.read().await.values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl<P: PollingProvider> PriceProvider for P {
+    async fn add_asset(&self, asset: Asset) -> Result<(), Error> {
+        if !self.prepare(&asset).await? {
+            return Ok(());
+        }
+
+        let state = self.state();
+        state.assets.write().await.insert(asset.id(), asset.clone());
+        state.assets_changed.notify_one();
+        info!("Added asset to {} provider: {:?}", self.name(), asset);
+        Ok(())
+    }
+
+    async fn remove_asset(&self, asset_id: AssetId) -> Result<(), Error> {
+        let state = self.state();
+        let removed = state.assets.write().await.remove(&asset_id);
+        self.forget(&asset_id).await;
+
+        if removed.is_some() {
+            state.assets_changed.notify_one();
+            info!("Removed asset from {} provider: {asset_id}", self.name());
+        }
+        Ok(())
+    }
+
+    async fn set_fetch_interval(&self, interval: u64) -> Result<(), Error> {
+        self.state().fetch_interval.send_replace(interval);
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>> {
+        broadcast_stream(&self.state().sender)
+    }
+
+    fn health(&self) -> ProviderHealth {
+        self.state().health.report()
+    }
+
+    fn start(&self) -> JoinHandle<Result<(), Error>> {
+        self.run()
+    }
+}
+
+/// Spawn the polling loop of a provider, broadcasting every fetched price
+/// through `sender`. The loop picks up changes to `fetch_interval` (in seconds)
+/// while running.
+///
+/// A fetch failing with a transient error is retried following `retry`, while
+/// other errors wait for the next tick. The loop never stops on errors, but
+/// skips ticks while the circuit of `health` is open.
+pub fn spawn<P: PollingProvider>(
+    provider: P,
+    sender: Sender<AssetPriceEvent>,
+    fetch_interval: &watch::Sender<u64>,
+    retry: &RetryPolicy,
+    health: &HealthTracker,
+) -> JoinHandle<Result<(), Error>> {
+    let retry = retry.clone();
+    let health = health.clone();
+    let mut interval_updates = fetch_interval.subscribe();
+    let span = info_span!("price_provider", price_provider = provider.name()).or_current();
+
+    tokio::spawn(
+        async move {
+            let name = provider.name().to_string();
+
+            let mut interval = fetch_timer(*interval_updates.borrow_and_update());
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    Ok(()) = interval_updates.changed() => {
+                        let seconds = *interval_updates.borrow_and_update();
+                        info!("{name} fetch interval updated to {seconds}s");
+                        interval = fetch_timer(seconds);
+                        continue;
+                    }
+                }
+
+                if !health.allow_request() {
+                    debug!("{name} circuit is open, skipping fetch");
+                    continue;
+                }
+
+                let times_fetched_counter = telemetry::get_meter_provider()
+                    .meter("shogun")
+                    .u64_counter("times_fetched_counter")
+                    .with_description("Number of times a price provider has been fetched")
+                    .build();
+
+                times_fetched_counter.add(1, &[KeyValue::new("provider", name.clone())]);
+
+                let started_at = Instant::now();
+                let price_events = match fetch_with_retry(&provider, &retry).await {
+                    Ok(price_events) => price_events,
+                    Err(e) => {
+                        health.record_failure(&e);
+                        continue;
+                    }
+                };
+
+                let oldest = price_events.iter().map(|event| event.fetched_at).min();
+                health.record_success(started_at.elapsed(), oldest);
+
+                info!("Fetched {} price events from {name}", price_events.len());
+
+                for event in price_events {
+                    if let Err(e) = sender.send(event) {
+                        error!("Failed to broadcast price event: {}", e);
+                    }
+                }
+            }
+        }
+        .instrument(span),
+    )
+}
+
+/// Timer ticking every `seconds`. Ticks missed while a fetch is retried are
+/// delayed rather than fired in a burst once it is done.
+fn fetch_timer(seconds: u64) -> Interval {
+    let mut interval = tokio::time::interval(Duration::from_secs(seconds));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
+
+/// Merge the prices of requests sent independently, so that a failing request
+/// does not drop the prices of the others. Fails only when every request
+/// failed, with the error of the last one.
+pub fn merge_results(
+    provider: &str,
+    results: impl IntoIterator<Item = Result<Vec<AssetPriceEvent>, Error>>,
+) -> Result<Vec<AssetPriceEvent>, Error> {
+    let mut events = Vec::new();
+    let mut total = 0;
+    let mut failed = 0;
+    let mut last_error = None;
+
+    for result in results {
+        total += 1;
+
+        match result {
+            Ok(result_events) => events.extend(result_events),
+            Err(e) => {
+                warn!("{provider} request failed: {e:?}");
+                failed += 1;
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if failed == total => Err(e),
+        _ => {
+            if failed > 0 {
+                warn!("{failed} of {total} {provider} requests failed, publishing the others");
+            }
+
+            Ok(events)
+        }
+    }
+}
+
+/// Fetch prices, retrying transient errors with backoff up to
+/// `retry.max_attempts` times
+async fn fetch_with_retry<P: PollingProvider>(
+    provider: &P,
+    retry: &RetryPolicy,
+) -> std::result::Result<Vec<AssetPriceEvent>, Report<Error>> {
+    let mut attempt = 1;
+
+    loop {
+        let e = match provider.fetch().await {
+            Ok(price_events) => return Ok(price_events),
+            Err(e) => e,
+        };
+
+        if !is_transient(&e) {
+            error!(
+                "Failed to fetch asset prices from {}, not retrying: {e:?}",
+                provider.name()
+            );
+            return Err(e);
+        }
+
+        if attempt >= retry.max_attempts {
+            error!(
+                "Failed to fetch asset prices from {} after {attempt} attempt(s): {e:?}",
+                provider.name()
+            );
+            return Err(e);
+        }
+
+        let delay = backoff(retry, attempt);
+        warn!(
+            "Failed to fetch asset prices from {} (attempt {attempt} of {}), retrying in {delay:?}: {e}",
+            provider.name(),
+            retry.max_attempts
+        );
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,364 @@
+use super::polling::{self, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use super::streaming;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::config::providers::{PythConfig, PythMode};
+use crate::services::ServiceProvider;
+use crate::{asset::Asset, config::ConfigService};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use error_stack::{Report, Result, ResultExt};
+use futures_util::StreamExt;
+use lib::error::Error;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prices assets through the Pyth Hermes API, along with Pyth's confidence
+/// interval. Only assets with a `providers.pyth` feed id are priced.
+#[derive(Clone, Debug)]
+pub struct PythProvider {
+    state: PollingState,
+    rate_limiter: RateLimiter,
+    client: reqwest::Client,
+    config: PythConfig,
+}
+
+impl PythProvider {
+    pub async fn new(services: ServiceProvider, config: PythConfig) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+
+        // The stream is long lived, so only polling requests are bounded
+        let client = match config.mode {
+            PythMode::Poll => reqwest::Client::builder().timeout(REQUEST_TIMEOUT),
+            PythMode::Stream => reqwest::Client::builder().connect_timeout(REQUEST_TIMEOUT),
+        }
+        .build()
+        .change_context(Error::Unknown)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            client,
+            config,
+        })
+    }
+
+    /// Tracked assets by normalized feed id
+    async fn feeds(&self) -> HashMap<String, Asset> {
+        self.state
+            .assets
+            .read()
+            .await
+            .values()
+            .filter_map(|asset| {
+                let feed_id = asset.providers.pyth.as_ref()?;
+                Some((normalize_feed_id(feed_id), asset.clone()))
+            })
+            .collect()
+    }
+
+    fn feeds_query(feeds: &HashMap<String, Asset>) -> Vec<(&'static str, String)> {
+        feeds
+            .keys()
+            .map(|feed_id| ("ids[]", format!("0x{feed_id}")))
+            .chain(std::iter::once(("parsed", String::from("true"))))
+            .collect()
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let feeds = self.feeds().await;
+
+        if feeds.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching Pyth prices for {:?} feeds", feeds.len());
+
+        let url = format!(
+            "{base_url}/v2/updates/price/latest",
+            base_url = self.config.base_url.trim_end_matches('/')
+        );
+
+        let update = self
+            .rate_limiter
+            .send(self.client.get(&url).query(&Self::feeds_query(&feeds)))
+            .await
+            .attach_printable_lazy(|| format!("Pyth request to {url} failed"))?
+            .json::<PriceUpdate>()
+            .await
+            .change_context(Error::Deserialization)?;
+
+        Ok(price_events(update, &feeds))
+    }
+
+    async fn stream_feeds(&self, feeds: &HashMap<String, Asset>) -> Result<(), Error> {
+        let url = format!(
+            "{base_url}/v2/updates/price/stream",
+            base_url = self.config.base_url.trim_end_matches('/')
+        );
+
+        // Hermes may stall without closing the connection, which is then
+        // reconnected once no data was received for `stream_idle_secs`
+        let idle_timeout = Duration::from_secs(self.config.stream_idle_secs);
+        let stalled = || {
+            Report::new(Error::FetchError).attach_printable(format!(
+                "No Pyth price stream data for {}s",
+                idle_timeout.as_secs()
+            ))
+        };
+
+        let connecting_at = Instant::now();
+        let request = self
+            .rate_limiter
+            .send(self.client.get(&url).query(&Self::feeds_query(feeds)));
+        let response = tokio::time::timeout(idle_timeout, request)
+            .await
+            .map_err(|_| stalled())?
+            .attach_printable_lazy(|| format!("Pyth request to {url} failed"))?;
+
+        self.state
+            .health
+            .record_success(connecting_at.elapsed(), None);
+        info!("Subscribed to Pyth price stream for {} feeds", feeds.len());
+
+        let mut chunks = response.bytes_stream();
+        let mut buffer = String::new();
+
+        while let Some(chunk) = tokio::time::timeout(idle_timeout, chunks.next())
+            .await
+            .map_err(|_| stalled())?
+        {
+            let chunk = chunk.change_context(Error::FetchError)?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(message) = next_event(&mut buffer) {
+                let data = message
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .map(str::trim)
+                    .collect::<String>();
+
+                if data.is_empty() {
+                    continue;
+                }
+
+                match serde_json::from_str::<PriceUpdate>(&data) {
+                    Ok(update) => {
+                        for event in price_events(update, feeds) {
+                            if let Err(e) = self.state.sender.send(event) {
+                                tracing::error!("Failed to broadcast price event: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) => error!("Failed to parse Pyth price update: {e}"),
+                }
+            }
+        }
+
+        Err(Report::new(Error::FetchError).attach_printable("Pyth price stream ended"))
+    }
+
+    /// Receive prices from the Hermes stream, resubscribing whenever the
+    /// tracked feeds change
+    async fn stream(&self) -> Result<(), Error> {
+        let feeds = self.feeds().await;
+
+        if feeds.is_empty() {
+            self.state.assets_changed.notified().await;
+            return Ok(());
+        }
+
+        streaming::until_assets_change(
+            Self::NAME,
+            &self.state.assets_changed,
+            self.stream_feeds(&feeds),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl PollingProvider for PythProvider {
+    const NAME: &'static str = "pyth";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.pyth.is_none() {
+            info!(
+                "Asset has no Pyth feed id, skipping in PythProvider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Polls Hermes, or keeps a price stream open, depending on `mode`
+    fn run(&self) -> JoinHandle<Result<(), Error>> {
+        match self.config.mode {
+            PythMode::Poll => polling::spawn(
+                self.clone(),
+                self.state.sender.clone(),
+                &self.state.fetch_interval,
+                &self.state.retry,
+                &self.state.health,
+            ),
+            PythMode::Stream => {
+                let provider = self.clone();
+
+                streaming::spawn(Self::NAME, &self.state.health, move || {
+                    let provider = provider.clone();
+                    async move { provider.stream().await }
+                })
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PriceUpdate {
+    #[serde(default)]
+    parsed: Vec<ParsedPriceFeed>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ParsedPriceFeed {
+    id: String,
+    price: PythPrice,
+}
+
+/// Pyth price, where the actual value is `price * 10^expo`
+#[derive(Debug, Serialize, Deserialize)]
+struct PythPrice {
+    price: String,
+    conf: String,
+    expo: i32,
+    publish_time: i64,
+}
+
+/// Lowercase feed id without `0x` prefix, as Hermes returns it
+fn normalize_feed_id(feed_id: &str) -> String {
+    feed_id.trim_start_matches("0x").to_lowercase()
+}
+
+/// Scale a raw Pyth value by its exponent, `None` when it is not a number or
+/// does not fit a `Decimal` (more than 28 decimals)
+fn scale(value: &str, expo: i32) -> Option<Decimal> {
+    let value = value.parse::<i64>().ok()?;
+
+    match expo {
+        expo if expo <= 0 => {
+            Decimal::try_from_i128_with_scale(value.into(), expo.unsigned_abs()).ok()
+        }
+        expo => Decimal::from(value).checked_mul(Decimal::from(10_i64.checked_pow(expo as u32)?)),
+    }
+}
+
+/// Remove the next complete server-sent event from `buffer`, events being
+/// separated by a blank line. CRLF line endings are normalized first, as a
+/// proxy can send them instead of LF.
+fn next_event(buffer: &mut String) -> Option<String> {
+    if buffer.contains('\r') {
+        *buffer = buffer.replace("\r\n", "\n");
+    }
+
+    let end = buffer.find("\n\n")?;
+    let event = buffer[..end].to_string();
+    buffer.drain(..end + 2);
+
+    Some(event)
+}
+
+fn price_events(update: PriceUpdate, feeds: &HashMap<String, Asset>) -> Vec<AssetPriceEvent> {
+    update
+        .parsed
+        .into_iter()
+        .filter_map(|feed| {
+            let Some(asset) = feeds.get(&normalize_feed_id(&feed.id)) else {
+                tracing::error!("Failed to find asset with Pyth feed id: {}", feed.id);
+                return None;
+            };
+
+            let Some(price) = scale(&feed.price.price, feed.price.expo) else {
+                error!(
+                    "Failed to parse Pyth price {} with exponent {} of feed {}",
+                    feed.price.price, feed.price.expo, feed.id
+                );
+                return None;
+            };
+            let confidence = scale(&feed.price.conf, feed.price.expo);
+            let fetched_at = Utc
+                .timestamp_opt(feed.price.publish_time, 0)
+                .single()
+                .unwrap_or_else(Utc::now);
+
+            Some(AssetPriceEvent {
+                provider: AssetPriceProvider::Pyth,
+                asset: asset.clone(),
+                price,
+                fetched_at,
+                metadata: PriceMetadata {
+                    confidence,
+                    exponent: Some(feed.price.expo),
+                    ..Default::default()
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn scale_applies_the_exponent() {
+        assert_eq!(scale("123456", -2), Decimal::from_str("1234.56").ok());
+        assert_eq!(scale("-5", 0), Some(Decimal::from(-5)));
+        assert_eq!(scale("5", 3), Some(Decimal::from(5000)));
+    }
+
+    #[test]
+    fn next_event_splits_on_lf_and_crlf() {
+        let mut buffer = String::from("data: 1\n\ndata: 2\r\n\r\ndata: 3\r");
+        assert_eq!(next_event(&mut buffer).as_deref(), Some("data: 1"));
+        assert_eq!(next_event(&mut buffer).as_deref(), Some("data: 2"));
+        assert_eq!(next_event(&mut buffer), None);
+
+        // A CRLF split across chunks
+        buffer.push_str("\n\r\n");
+        assert_eq!(next_event(&mut buffer).as_deref(), Some("data: 3"));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn scale_rejects_values_out_of_range() {
+        assert_eq!(
+            scale("1", -28),
+            Decimal::from_str("0.0000000000000000000000000001").ok()
+        );
+        assert_eq!(scale("1", -29), None);
+        assert_eq!(scale("1", i32::MIN), None);
+        assert_eq!(scale("1", 30), None);
+        assert_eq!(scale("1.5", -2), None);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::config::RateLimitConfig;
+use crate::telemetry;
+use chrono::{DateTime, NaiveDate, Utc};
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use opentelemetry::KeyValue;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Token bucket through which a provider sends its HTTP requests or RPC calls,
+/// also pausing them while a 429 response's `Retry-After` runs
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    provider: String,
+    config: Option<RateLimitConfig>,
+    state: Arc<Mutex<BucketState>>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Set by a `Retry-After` header
+    paused_until: Option<Instant>,
+    /// UTC day `used_today` counts the requests of
+    day: NaiveDate,
+    used_today: u64,
+}
+
+impl RateLimiter {
+    /// Limiter of the given provider, only honoring `Retry-After` without a config
+    pub fn new(provider: impl Into<String>, config: Option<RateLimitConfig>) -> Self {
+        let burst = config.as_ref().map(burst).unwrap_or_default();
+
+        Self {
+            provider: provider.into(),
+            config,
+            state: Arc::new(Mutex::new(BucketState {
+                tokens: burst,
+                refilled_at: Instant::now(),
+                paused_until: None,
+                day: Utc::now().date_naive(),
+                used_today: 0,
+            })),
+        }
+    }
+
+    /// Send a request once the limits allow it, failing with
+    /// `Error::FetchError` on an error status
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        self.acquire().await?;
+
+        let response = request.send().await.change_context(Error::FetchError)?;
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            self.count("too_many_requests");
+
+            if let Some(delay) = retry_after(&response) {
+                warn!(
+                    "{} is rate limited, pausing requests for {}s",
+                    self.provider,
+                    delay.as_secs()
+                );
+                self.lock().paused_until = Some(Instant::now() + delay);
+            }
+        }
+
+        response
+            .error_for_status()
+            .change_context(Error::FetchError)
+    }
+
+    /// Wait for a token, failing with `Error::RateLimited` once the daily quota
+    /// is used up
+    pub async fn acquire(&self) -> Result<(), Error> {
+        let mut throttled = false;
+
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                let now = Instant::now();
+
+                match state.paused_until.filter(|until| *until > now) {
+                    Some(until) => until - now,
+                    None => match &self.config {
+                        Some(config) => {
+                            match take_token(&mut state, config, now, Utc::now().date_naive()) {
+                                Ok(wait) => wait,
+                                Err(quota) => {
+                                    self.count("daily_quota");
+                                    return Err(Report::new(Error::RateLimited).attach_printable(
+                                        format!(
+                                            "{} daily quota of {quota} requests is used up",
+                                            self.provider
+                                        ),
+                                    ));
+                                }
+                            }
+                        }
+                        None => Duration::ZERO,
+                    },
+                }
+            };
+
+            if wait.is_zero() {
+                return Ok(());
+            }
+
+            if !throttled {
+                throttled = true;
+                self.count("throttled");
+                debug!("Throttling {} request for {wait:?}", self.provider);
+            }
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn count(&self, reason: &'static str) {
+        let rate_limited_counter = telemetry::get_meter_provider()
+            .meter("shogun")
+            .u64_counter("rate_limited_requests")
+            .with_description("Number of provider requests delayed or refused by rate limits")
+            .build();
+
+        rate_limited_counter.add(
+            1,
+            &[
+                KeyValue::new("provider", self.provider.clone()),
+                KeyValue::new("reason", reason),
+            ],
+        );
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Take a token if one is available, returning how long to wait for the next
+/// one otherwise, or the daily quota once it is used up
+fn take_token(
+    state: &mut BucketState,
+    config: &RateLimitConfig,
+    now: Instant,
+    today: NaiveDate,
+) -> std::result::Result<Duration, u64> {
+    if state.day != today {
+        state.day = today;
+        state.used_today = 0;
+    }
+
+    if let Some(quota) = config
+        .daily_quota
+        .filter(|quota| state.used_today >= *quota)
+    {
+        return Err(quota);
+    }
+
+    let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+    state.tokens = (state.tokens + elapsed * config.requests_per_second).min(burst(config));
+    state.refilled_at = now;
+
+    if state.tokens >= 1.0 {
+        state.tokens -= 1.0;
+        state.used_today += 1;
+        return Ok(Duration::ZERO);
+    }
+
+    Ok(Duration::from_secs_f64(
+        (1.0 - state.tokens) / config.requests_per_second,
+    ))
+}
+
+fn burst(config: &RateLimitConfig) -> f64 {
+    config
+        .burst
+        .map(f64::from)
+        .unwrap_or(config.requests_per_second.ceil())
+        .max(1.0)
+}
+
+/// Delay of a `Retry-After` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+/// Delay of a `Retry-After` value in seconds or as an HTTP date, `None` when
+/// the date already passed
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - now).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config(requests_per_second: f64, daily_quota: Option<u64>) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_second,
+            burst: Some(2),
+            daily_quota,
+        }
+    }
+
+    fn bucket(config: &RateLimitConfig, now: Instant, day: NaiveDate) -> BucketState {
+        BucketState {
+            tokens: burst(config),
+            refilled_at: now,
+            paused_until: None,
+            day,
+            used_today: 0,
+        }
+    }
+
+    #[test]
+    fn tokens_refill_at_the_configured_rate_up_to_the_burst() {
+        let config = config(0.5, None);
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let start = Instant::now();
+        let mut state = bucket(&config, start, today);
+
+        assert_eq!(
+            take_token(&mut state, &config, start, today),
+            Ok(Duration::ZERO)
+        );
+        assert_eq!(
+            take_token(&mut state, &config, start, today),
+            Ok(Duration::ZERO)
+        );
+        assert_eq!(
+            take_token(&mut state, &config, start, today),
+            Ok(Duration::from_secs(2))
+        );
+
+        let later = start + Duration::from_secs(1);
+        assert_eq!(
+            take_token(&mut state, &config, later, today),
+            Ok(Duration::from_secs(1))
+        );
+
+        let later = start + Duration::from_secs(2);
+        assert_eq!(
+            take_token(&mut state, &config, later, today),
+            Ok(Duration::ZERO)
+        );
+
+        // Idle time doesn't refill more than the burst
+        let idle = later + Duration::from_secs(60);
+        for _ in 0..2 {
+            assert_eq!(
+                take_token(&mut state, &config, idle, today),
+                Ok(Duration::ZERO)
+            );
+        }
+        assert!(take_token(&mut state, &config, idle, today).unwrap() > Duration::ZERO);
+    }
+
+    #[test]
+    fn daily_quota_refuses_requests_until_the_next_day() {
+        let config = config(100.0, Some(3));
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let start = Instant::now();
+        let mut state = bucket(&config, start, today);
+
+        for second in 0..3 {
+            let now = start + Duration::from_secs(second);
+            assert_eq!(
+                take_token(&mut state, &config, now, today),
+                Ok(Duration::ZERO)
+            );
+        }
+
+        let later = start + Duration::from_secs(10);
+        assert_eq!(take_token(&mut state, &config, later, today), Err(3));
+
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(
+            take_token(&mut state, &config, later, tomorrow),
+            Ok(Duration::ZERO)
+        );
+        assert_eq!(state.used_today, 1);
+    }
+
+    #[test]
+    fn retry_after_is_read_in_seconds_or_as_an_http_date() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 11:59:00 GMT", now),
+            None
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -0,0 +1,166 @@
+use super::broadcast_stream;
+use super::health::{HealthTracker, ProviderHealth};
+use crate::asset::price::price_provider::{AssetPriceEvent, PriceProvider};
+use crate::asset::price::recorder::RecordedEvent;
+use crate::asset::{Asset, AssetId};
+use crate::config::providers::ReplayConfig;
+use crate::config::ConfigService;
+use crate::services::ServiceProvider;
+use async_trait::async_trait;
+use chrono::Utc;
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
+use tokio::sync::broadcast::{self as broadcast, Sender};
+use tokio::sync::RwLock;
+use tokio::time::{sleep, sleep_until, Instant};
+use tokio_stream::Stream;
+use tracing::{error, info};
+
+/// Queued events above which an as fast as possible replay waits for subscribers,
+/// so they don't lag behind and miss events
+const MAX_BACKLOG: usize = 50;
+
+/// Plays back price events recorded by `[tasks.recorder]`, once, at the pace
+/// they were recorded at scaled by `speed`. Events keep their original provider
+/// and are only replayed for tracked assets. They are not recorded again.
+#[derive(Clone, Debug)]
+pub struct ReplayProvider {
+    assets: Arc<RwLock<HashMap<AssetId, Asset>>>,
+    sender: Sender<AssetPriceEvent>,
+    health: HealthTracker,
+    config: ReplayConfig,
+}
+
+impl ReplayProvider {
+    pub async fn new(services: ServiceProvider, config: ReplayConfig) -> Self {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+        let (sender, _) = broadcast::channel::<AssetPriceEvent>(100);
+
+        Self {
+            sender,
+            health: HealthTracker::new("replay", service_config.health.clone()),
+            assets: Arc::new(RwLock::new(HashMap::new())),
+            config,
+        }
+    }
+
+    async fn replay(&self) -> Result<(), Error> {
+        let reading_at = std::time::Instant::now();
+        let events = read_recording(&self.config.path).await?;
+        self.health.record_success(reading_at.elapsed(), None);
+
+        info!(
+            "Replaying {} price events from {} at speed {}",
+            events.len(),
+            self.config.path,
+            self.config.speed
+        );
+
+        let Some(first_at) = events.first().map(|recorded| recorded.recorded_at) else {
+            return Ok(());
+        };
+        let started_at = Instant::now();
+
+        for RecordedEvent {
+            recorded_at,
+            mut event,
+        } in events
+        {
+            if self.config.speed > 0.0 {
+                let offset = (recorded_at - first_at).to_std().unwrap_or_default();
+                sleep_until(started_at + offset.div_f64(self.config.speed)).await;
+            } else {
+                while self.sender.len() >= MAX_BACKLOG {
+                    sleep(Duration::from_millis(1)).await;
+                }
+            }
+
+            let Some(asset) = self.assets.read().await.get(&event.asset.id()).cloned() else {
+                continue;
+            };
+
+            // Keep the age the price had when it was recorded
+            event.asset = asset;
+            if self.config.shift_timestamps {
+                event.fetched_at = Utc::now() - (recorded_at - event.fetched_at);
+            }
+
+            if let Err(e) = self.sender.send(event) {
+                error!("Failed to broadcast price event: {}", e);
+            }
+        }
+
+        info!("Finished replaying {}", self.config.path);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PriceProvider for ReplayProvider {
+    async fn add_asset(&self, asset: Asset) -> Result<(), Error> {
+        let mut assets = self.assets.write().await;
+        assets.insert(asset.id(), asset.clone());
+        info!("Added asset to ReplayProvider: {:?}", asset);
+        Ok(())
+    }
+
+    async fn remove_asset(&self, asset_id: AssetId) -> Result<(), Error> {
+        let mut assets = self.assets.write().await;
+        assets.remove(&asset_id);
+        info!("Removed asset from ReplayProvider: {asset_id}");
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>> {
+        broadcast_stream(&self.sender)
+    }
+
+    fn health(&self) -> ProviderHealth {
+        self.health.report()
+    }
+
+    fn is_recorded(&self) -> bool {
+        false
+    }
+
+    fn start(&self) -> tokio::task::JoinHandle<Result<(), Error>> {
+        let provider = self.clone();
+
+        tokio::spawn(async move {
+            let result = provider.replay().await;
+            if let Err(e) = &result {
+                error!("Failed to replay price events: {e:?}");
+                provider.health.record_failure(e);
+            }
+            result
+        })
+    }
+}
+
+/// Read the events of a recording, in the order they were recorded
+async fn read_recording(path: &str) -> Result<Vec<RecordedEvent>, Error> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .change_context(Error::ReadConfig)
+        .attach_printable_lazy(|| format!("Failed to read price recording {path}"))?;
+
+    let mut events = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str::<RecordedEvent>(line).map_err(|e| {
+                Report::new(Error::Deserialization).attach_printable(format!(
+                    "Invalid price recording line {} of {path}: {e}",
+                    index + 1
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Appended lines are in order, unless several recordings were concatenated
+    events.sort_by_key(|recorded| recorded.recorded_at);
+
+    Ok(events)
+}
@@ -0,0 +1,147 @@
+use crate::config::RetryPolicy;
+use error_stack::Report;
+use lib::error::Error;
+use rand::Rng;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Whether a failed fetch may succeed if retried: timeouts, connection
+/// errors, 5xx and 429 responses. Invalid requests, responses that can't be
+/// parsed, config problems, used up daily quotas and unknown errors fail the
+/// same way every time.
+pub fn is_transient(report: &Report<Error>) -> bool {
+    if let Some(error) = report.downcast_ref::<reqwest::Error>() {
+        return match error.status() {
+            Some(status) => {
+                status.is_server_error()
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::REQUEST_TIMEOUT
+            }
+            None => !(error.is_decode() || error.is_builder() || error.is_redirect()),
+        };
+    }
+
+    matches!(report.current_context(), Error::FetchError)
+}
+
+/// Delay before retrying after the given failed attempt, starting at 1
+pub fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let exponential = policy
+        .initial_backoff_ms
+        .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+        .min(policy.max_backoff_ms);
+
+    let jitter = match policy.jitter > 0.0 {
+        true => rand::thread_rng().gen_range(-policy.jitter..=policy.jitter),
+        false => 0.0,
+    };
+
+    Duration::from_millis(exponential).mul_f64((1.0 + jitter).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error_stack::{Result, ResultExt};
+    use wiremock::matchers::any;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Error of a request answered with `response`, wrapped as providers do
+    async fn fetch_error(response: ResponseTemplate) -> Report<Error> {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(response)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let fetch = async {
+            client
+                .get(server.uri())
+                .send()
+                .await
+                .change_context(Error::FetchError)?
+                .error_for_status()
+                .change_context(Error::FetchError)?
+                .json::<u64>()
+                .await
+                .change_context(Error::Deserialization)
+        };
+        let result: Result<u64, Error> = fetch.await;
+
+        result.unwrap_err()
+    }
+
+    fn policy(jitter: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 500,
+            jitter,
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_rate_limits_and_timeouts_are_transient() {
+        for status in [500, 502, 503, 429, 408] {
+            let error = fetch_error(ResponseTemplate::new(status)).await;
+            assert!(is_transient(&error), "{status}");
+        }
+
+        let slow = ResponseTemplate::new(200)
+            .set_body_string("1")
+            .set_delay(Duration::from_secs(2));
+        assert!(is_transient(&fetch_error(slow).await));
+    }
+
+    #[tokio::test]
+    async fn client_and_decode_errors_are_permanent() {
+        for status in [400, 401, 403, 404] {
+            let error = fetch_error(ResponseTemplate::new(status)).await;
+            assert!(!is_transient(&error), "{status}");
+        }
+
+        let not_json = ResponseTemplate::new(200).set_body_string("not json");
+        assert!(!is_transient(&fetch_error(not_json).await));
+    }
+
+    #[test]
+    fn errors_are_classified_by_context_without_a_response() {
+        assert!(is_transient(&Report::new(Error::FetchError)));
+
+        for error in [
+            Error::RateLimited,
+            Error::InvalidConfig,
+            Error::Deserialization,
+            Error::Unknown,
+        ] {
+            assert!(!is_transient(&Report::new(error)));
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let policy = policy(0.0);
+        let delays = (1..=6)
+            .map(|attempt| backoff(&policy, attempt).as_millis())
+            .collect::<Vec<_>>();
+
+        assert_eq!(delays, [100, 200, 400, 500, 500, 500]);
+        assert_eq!(backoff(&policy, u32::MAX), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn backoff_jitter_stays_within_bounds() {
+        let policy = policy(0.2);
+
+        for _ in 0..100 {
+            let delay = backoff(&policy, 2);
+            assert!(delay >= Duration::from_millis(160), "{delay:?}");
+            assert!(delay <= Duration::from_millis(240), "{delay:?}");
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::broadcast_stream;
+use super::health::{HealthTracker, ProviderHealth};
+use crate::asset::price::price_provider::{AssetPriceEvent, PriceProvider};
+use crate::asset::{Asset, AssetId};
+use crate::config::ConfigService;
+use async_trait::async_trait;
+use error_stack::Result;
+use lib::error::Error;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self as broadcast, Sender};
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+use tracing::{error, info, info_span, Instrument};
+
+/// Delay before the first reconnection attempt, doubled after every failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Price provider receiving prices over a long lived connection.
+///
+/// Streaming providers implement `PriceProvider` through `Streaming` and their
+/// `StreamingState`, and only decide which assets they price and how to
+/// handle the messages of their connection.
+#[async_trait]
+pub trait StreamingProvider: Clone + Send + Sync + 'static {
+    /// Provider name used in logs and spans
+    const NAME: &'static str;
+
+    fn state(&self) -> &StreamingState;
+
+    /// Check the provider can price an asset before it is tracked, `Ok(false)`
+    /// skipping an asset the provider doesn't price
+    async fn prepare(&self, _asset: &Asset) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    /// Connect, subscribing to the tracked `assets`, and broadcast every
+    /// received price until the connection drops. Providers record a success
+    /// in their `HealthTracker` once connected, errors are recorded by `spawn`.
+    async fn stream(&self, assets: &[Asset]) -> Result<(), Error>;
+}
+
+/// Tracked assets, price channel and health of a streaming provider
+#[derive(Clone, Debug)]
+pub struct StreamingState {
+    pub assets: Arc<RwLock<HashMap<AssetId, Asset>>>,
+    pub sender: Sender<AssetPriceEvent>,
+    pub health: HealthTracker,
+    /// Notified when the tracked assets change, so the stream resubscribes
+    pub assets_changed: Arc<Notify>,
+}
+
+impl StreamingState {
+    pub fn new(name: &str, config: &ConfigService) -> Self {
+        let (sender, _) = broadcast::channel::<AssetPriceEvent>(100);
+
+        Self {
+            assets: Arc::new(RwLock::new(HashMap::new())),
+            sender,
+            health: HealthTracker::new(name, config.health.clone()),
+            assets_changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Snapshot of the tracked assets
+    pub async fn assets(&self) -> Vec<Asset> {
+        self.assets.read().await.values().cloned().collect()
+    }
+
+    pub fn send(&self, event: AssetPriceEvent) {
+        if let Err(e) = self.sender.send(event) {
+            error!("Failed to broadcast price event: {}", e);
+        }
+    }
+}
+
+/// `PriceProvider` of a `StreamingProvider`. Polling providers already get
+/// theirs from a blanket implementation, which a second blanket implementation
+/// would conflict with, so streaming providers are wrapped instead.
+#[derive(Clone, Debug)]
+pub struct Streaming<P>(P);
+
+impl<P: StreamingProvider> Streaming<P> {
+    pub fn new(provider: P) -> Self {
+        Self(provider)
+    }
+}
+
+#[async_trait]
+impl<P: StreamingProvider> PriceProvider for Streaming<P> {
+    async fn add_asset(&self, asset: Asset) -> Result<(), Error> {
+        if !self.0.prepare(&asset).await? {
+            return Ok(());
+        }
+
+        let state = self.0.state();
+        state.assets.write().await.insert(asset.id(), asset.clone());
+        state.assets_changed.notify_one();
+        info!("Added asset to {} provider: {:?}", P::NAME, asset);
+        Ok(())
+    }
+
+    async fn remove_asset(&self, asset_id: AssetId) -> Result<(), Error> {
+        let state = self.0.state();
+
+        if state.assets.write().await.remove(&asset_id).is_some() {
+            state.assets_changed.notify_one();
+            info!("Removed asset from {} provider: {asset_id}", P::NAME);
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>> {
+        broadcast_stream(&self.0.state().sender)
+    }
+
+    fn health(&self) -> ProviderHealth {
+        self.0.state().health.report()
+    }
+
+    fn start(&self) -> JoinHandle<Result<(), Error>> {
+        let provider = self.0.clone();
+
+        spawn(P::NAME, &self.0.state().health, move || {
+            let provider = provider.clone();
+            async move { connect(&provider).await }
+        })
+    }
+}
+
+/// One connection of a streaming provider, waiting for an asset while none is
+/// tracked
+async fn connect<P: StreamingProvider>(provider: &P) -> Result<(), Error> {
+    let state = provider.state();
+    let assets = state.assets().await;
+
+    if assets.is_empty() {
+        state.assets_changed.notified().await;
+        return Ok(());
+    }
+
+    until_assets_change(P::NAME, &state.assets_changed, provider.stream(&assets)).await
+}
+
+/// Run `stream` until it ends, or until `assets_changed` is notified so the
+/// stream resubscribes to the new assets
+pub async fn until_assets_change(
+    name: &str,
+    assets_changed: &Notify,
+    stream: impl Future<Output = Result<(), Error>>,
+) -> Result<(), Error> {
+    tokio::select! {
+        result = stream => result,
+        _ = assets_changed.notified() => {
+            info!("{name} assets changed, resubscribing");
+            Ok(())
+        }
+    }
+}
+
+/// Spawn the connection loop of a streaming provider, calling `connect` again
+/// whenever a connection ends. `Ok` reconnects right away, e.g. when the
+/// tracked assets changed, while errors reconnect after an exponential
+/// backoff, and only probe the provider once its circuit is open.
+pub fn spawn<F, Fut>(
+    name: &'static str,
+    health: &HealthTracker,
+    connect: F,
+) -> JoinHandle<Result<(), Error>>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), Error>> + Send + 'static,
+{
+    let span = info_span!("price_provider", price_provider = name).or_current();
+
+    tokio::spawn(run(name, health.clone(), connect).instrument(span))
+}
+
+async fn run<F, Fut>(name: &'static str, health: HealthTracker, connect: F) -> Result<(), Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        while let Some(remaining) = health.open_remaining() {
+            info!(
+                "{name} circuit is open, probing again in {}s",
+                remaining.as_secs()
+            );
+            tokio::time::sleep(remaining).await;
+        }
+
+        let connected_at = Instant::now();
+
+        match connect().await {
+            Ok(()) => {
+                backoff = INITIAL_BACKOFF;
+                continue;
+            }
+            Err(e) => {
+                error!("{name} price stream failed: {e:?}");
+                health.record_failure(&e);
+            }
+        }
+
+        // A connection that stayed up for a while starts over from the initial backoff
+        if connected_at.elapsed() > MAX_BACKOFF {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        info!(
+            "Reconnecting to {name} price stream in {}s",
+            backoff.as_secs()
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,280 @@
+use super::evm::{parse_address, EvmClients, PoolTokens};
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::aggregator::PriceAggregator;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::config::providers::UniswapV2Config;
+use crate::services::ServiceProvider;
+use crate::{
+    asset::{Asset, AssetId},
+    config::ConfigService,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use error_stack::{Report, Result, ResultExt};
+use ethers::contract::abigen;
+use futures::future::join_all;
+use lib::error::Error;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::{sync::Arc, time::Duration};
+use tracing::{info, instrument, warn};
+
+abigen!(
+    UniswapV2Pair,
+    r#"[
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+    ]"#
+);
+
+/// Prices assets from the reserves of a Uniswap V2-compatible pair against a
+/// quote asset, converted to USD with the aggregated price of the quote asset.
+/// The USD value of the reserves is reported as liquidity. Assets are skipped
+/// while the quote asset price is missing or older than `quote_max_age`.
+#[derive(Clone, Debug)]
+pub struct UniswapV2Provider {
+    state: PollingState,
+    clients: EvmClients,
+    /// Limits the contract calls sent to the RPC urls
+    rate_limiter: RateLimiter,
+    aggregator: Arc<PriceAggregator>,
+    config: UniswapV2Config,
+    pair_tokens: PoolTokens,
+}
+
+impl UniswapV2Provider {
+    pub async fn new(services: ServiceProvider, config: UniswapV2Config) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+        let aggregator = services.get_service_unchecked::<PriceAggregator>().await;
+        let clients = EvmClients::new(&service_config.rpc)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            clients,
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            aggregator,
+            config,
+            pair_tokens: PoolTokens::default(),
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching Uniswap V2 prices for {:?} assets", assets.len());
+
+        // Assets without a fresh quote asset price are skipped
+        let results =
+            join_all(assets.iter().map(|asset| async move {
+                Ok(Vec::from_iter(self.fetch_asset_price(asset).await?))
+            }))
+            .await;
+
+        merge_results(Self::NAME, results)
+    }
+
+    /// Price an asset from its pair, or `None` without a fresh quote asset price
+    async fn fetch_asset_price(&self, asset: &Asset) -> Result<Option<AssetPriceEvent>, Error> {
+        let Some(pair_config) = &asset.providers.uniswap_v2 else {
+            return Ok(None);
+        };
+
+        let quote = AssetId::new(asset.chain.clone(), &pair_config.quote)?;
+        let max_age = Duration::from_secs(self.config.quote_max_age);
+        let Some(quote_price) = self.aggregator.latest(&quote, max_age).await else {
+            warn!(
+                "No aggregated price within {}s for quote asset {} of {}, skipping",
+                self.config.quote_max_age, pair_config.quote, asset.symbol
+            );
+            return Ok(None);
+        };
+
+        let client = self.clients.get(&asset.chain).ok_or_else(|| {
+            Report::new(Error::InvalidConfig)
+                .attach_printable(format!("No RPC url configured for {}", asset.chain))
+        })?;
+
+        let pair_address = parse_address(&pair_config.pair)?;
+        let asset_address = parse_address(&asset.address)?;
+        let quote_address = parse_address(&pair_config.quote)?;
+        let (token0, token1) = self
+            .pair_tokens
+            .get(
+                &asset.chain,
+                pair_address,
+                client.clone(),
+                &self.rate_limiter,
+            )
+            .await?;
+        let pair = UniswapV2Pair::new(pair_address, client);
+
+        let asset_is_token0 = match (asset_address, quote_address) {
+            (asset, quote) if asset == token0 && quote == token1 => true,
+            (asset, quote) if asset == token1 && quote == token0 => false,
+            _ => {
+                return Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                    "Pair {} doesn't pair {} with {}",
+                    pair_config.pair, asset.address, pair_config.quote
+                )))
+            }
+        };
+
+        self.rate_limiter.acquire().await?;
+        let (reserve0, reserve1, _) = pair
+            .get_reserves()
+            .call()
+            .await
+            .change_context(Error::FetchError)
+            .attach_printable_lazy(|| format!("Failed to read reserves of {pair_address:?}"))?;
+
+        let (price, liquidity) = reserves_price(
+            (reserve0, reserve1),
+            asset_is_token0,
+            asset.decimals,
+            quote_price.asset.decimals,
+            quote_price.price,
+        )
+        .attach_printable_lazy(|| {
+            format!(
+                "Failed to price {} from pair {}",
+                asset.symbol, pair_config.pair
+            )
+        })?;
+
+        Ok(Some(AssetPriceEvent {
+            provider: AssetPriceProvider::UniswapV2,
+            asset: asset.clone(),
+            price,
+            fetched_at: Utc::now(),
+            metadata: PriceMetadata {
+                liquidity: Some(liquidity),
+                ..Default::default()
+            },
+        }))
+    }
+}
+
+#[async_trait]
+impl PollingProvider for UniswapV2Provider {
+    const NAME: &'static str = "uniswap_v2";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.uniswap_v2.is_none() {
+            info!(
+                "Asset has no Uniswap V2 pair, skipping in UniswapV2Provider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        if self.clients.get(&asset.chain).is_none() {
+            return Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                "No RPC url configured for {}, can't price {} with Uniswap V2",
+                asset.chain, asset.symbol
+            )));
+        }
+
+        Ok(true)
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+/// USD price of the asset and USD liquidity of a pair from its `(reserve0, reserve1)`
+/// reserves, given the USD price of the quote asset
+fn reserves_price(
+    (reserve0, reserve1): (u128, u128),
+    asset_is_token0: bool,
+    asset_decimals: u8,
+    quote_decimals: u8,
+    quote_usd: Decimal,
+) -> Result<(Decimal, Decimal), Error> {
+    let (asset_reserve, quote_reserve) = match asset_is_token0 {
+        true => (reserve0, reserve1),
+        false => (reserve1, reserve0),
+    };
+
+    if asset_reserve == 0 || quote_reserve == 0 {
+        return Err(Report::new(Error::FetchError).attach_printable("Pair has no liquidity"));
+    }
+
+    let asset_amount = asset_reserve as f64 / 10f64.powi(asset_decimals as i32);
+    let quote_amount = quote_reserve as f64 / 10f64.powi(quote_decimals as i32);
+
+    let out_of_range = |value: f64| {
+        Report::new(Error::FetchError).attach_printable(format!("Value {value} out of range"))
+    };
+
+    let price_in_quote = quote_amount / asset_amount;
+    let price = Decimal::from_f64(price_in_quote)
+        .and_then(|price| price.checked_mul(quote_usd))
+        .ok_or_else(|| out_of_range(price_in_quote))?;
+
+    // Both sides of a constant-product pair hold the same value
+    let liquidity = Decimal::from_f64(quote_amount * 2.0)
+        .and_then(|amount| amount.checked_mul(quote_usd))
+        .ok_or_else(|| out_of_range(quote_amount * 2.0))?;
+
+    Ok((price, liquidity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20M USDC (6 decimals, token0) and 10k WETH (18 decimals, token1)
+    const USDC_WETH_RESERVES: (u128, u128) = (20_000_000 * 10u128.pow(6), 10_000 * 10u128.pow(18));
+
+    /// 100 WBTC (8 decimals, token0) and 2000 WETH (18 decimals, token1)
+    const WBTC_WETH_RESERVES: (u128, u128) = (100 * 10u128.pow(8), 2_000 * 10u128.pow(18));
+
+    #[test]
+    fn prices_both_token_orders() {
+        // WETH is token1, quoted in USDC at 1 USD
+        let (price, liquidity) =
+            reserves_price(USDC_WETH_RESERVES, false, 18, 6, Decimal::ONE).unwrap();
+        assert_eq!(price, Decimal::from(2000));
+        assert_eq!(liquidity, Decimal::from(40_000_000));
+
+        // USDC is token0, quoted in WETH at 2000 USD
+        let (price, liquidity) =
+            reserves_price(USDC_WETH_RESERVES, true, 6, 18, Decimal::from(2000)).unwrap();
+        assert_eq!(price, Decimal::ONE);
+        assert_eq!(liquidity, Decimal::from(40_000_000));
+    }
+
+    #[test]
+    fn prices_tokens_with_other_decimals() {
+        // WBTC is token0, quoted in WETH at 2000 USD
+        let (price, liquidity) =
+            reserves_price(WBTC_WETH_RESERVES, true, 8, 18, Decimal::from(2000)).unwrap();
+        assert_eq!(price, Decimal::from(40_000));
+        assert_eq!(liquidity, Decimal::from(8_000_000));
+
+        // WETH is token1, quoted in WBTC at 40000 USD
+        let (price, liquidity) =
+            reserves_price(WBTC_WETH_RESERVES, false, 18, 8, Decimal::from(40_000)).unwrap();
+        assert_eq!(price, Decimal::from(2000));
+        assert_eq!(liquidity, Decimal::from(8_000_000));
+    }
+
+    #[test]
+    fn rejects_empty_reserves() {
+        assert!(reserves_price((0, 1), true, 18, 18, Decimal::ONE).is_err());
+        assert!(reserves_price((1, 0), true, 18, 18, Decimal::ONE).is_err());
+    }
+}
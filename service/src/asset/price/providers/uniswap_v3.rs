@@ -0,0 +1,337 @@
+use super::evm::{parse_address, EvmClients, PoolTokens};
+use super::polling::{merge_results, PollingProvider, PollingState};
+use super::rate_limit::RateLimiter;
+use crate::asset::price::aggregator::PriceAggregator;
+use crate::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use crate::config::providers::UniswapV3Config;
+use crate::services::ServiceProvider;
+use crate::{
+    asset::{Asset, AssetId},
+    config::ConfigService,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use error_stack::{Report, Result, ResultExt};
+use ethers::contract::abigen;
+use ethers::types::U256;
+use futures::future::join_all;
+use lib::error::Error;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::{sync::Arc, time::Duration};
+use tracing::{info, instrument, warn};
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function observe(uint32[] secondsAgos) external view returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s)
+    ]"#
+);
+
+/// Prices assets from a Uniswap V3 pool against a quote asset, using the
+/// pool TWAP or spot price, and converts it to USD with the aggregated price
+/// of the quote asset. Assets are skipped while that price is missing or
+/// older than `quote_max_age`.
+#[derive(Clone, Debug)]
+pub struct UniswapV3Provider {
+    state: PollingState,
+    clients: EvmClients,
+    /// Limits the contract calls sent to the RPC urls
+    rate_limiter: RateLimiter,
+    aggregator: Arc<PriceAggregator>,
+    config: UniswapV3Config,
+    pool_tokens: PoolTokens,
+}
+
+impl UniswapV3Provider {
+    pub async fn new(services: ServiceProvider, config: UniswapV3Config) -> Result<Self, Error> {
+        let service_config = services.get_service_unchecked::<ConfigService>().await;
+        let aggregator = services.get_service_unchecked::<PriceAggregator>().await;
+        let clients = EvmClients::new(&service_config.rpc)?;
+
+        Ok(Self {
+            state: PollingState::new(Self::NAME, &service_config),
+            clients,
+            rate_limiter: RateLimiter::new(
+                Self::NAME,
+                service_config.rate_limits.get(Self::NAME).cloned(),
+            ),
+            aggregator,
+            config,
+            pool_tokens: PoolTokens::default(),
+        })
+    }
+
+    #[instrument(name = "fetch_asset_prices", skip(self))]
+    pub async fn fetch_asset_prices(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        let assets = self.state.assets().await;
+
+        if assets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        info!("Fetching Uniswap V3 prices for {:?} assets", assets.len());
+
+        // Assets without a fresh quote asset price are skipped
+        let results =
+            join_all(assets.iter().map(|asset| async move {
+                Ok(Vec::from_iter(self.fetch_asset_price(asset).await?))
+            }))
+            .await;
+
+        merge_results(Self::NAME, results)
+    }
+
+    /// Price an asset from its pool, or `None` without a fresh quote asset price
+    async fn fetch_asset_price(&self, asset: &Asset) -> Result<Option<AssetPriceEvent>, Error> {
+        let Some(pool_config) = &asset.providers.uniswap_v3 else {
+            return Ok(None);
+        };
+
+        let quote = AssetId::new(asset.chain.clone(), &pool_config.quote)?;
+        let max_age = Duration::from_secs(self.config.quote_max_age);
+        let Some(quote_price) = self.aggregator.latest(&quote, max_age).await else {
+            warn!(
+                "No aggregated price within {}s for quote asset {} of {}, skipping",
+                self.config.quote_max_age, pool_config.quote, asset.symbol
+            );
+            return Ok(None);
+        };
+
+        let client = self.clients.get(&asset.chain).ok_or_else(|| {
+            Report::new(Error::InvalidConfig)
+                .attach_printable(format!("No RPC url configured for {}", asset.chain))
+        })?;
+
+        let pool_address = parse_address(&pool_config.pool)?;
+        let asset_address = parse_address(&asset.address)?;
+        let (token0, token1) = self
+            .pool_tokens
+            .get(
+                &asset.chain,
+                pool_address,
+                client.clone(),
+                &self.rate_limiter,
+            )
+            .await?;
+        let pool = UniswapV3Pool::new(pool_address, client);
+        let quote_address = parse_address(&pool_config.quote)?;
+
+        let asset_is_token0 = match (asset_address, quote_address) {
+            (asset, quote) if asset == token0 && quote == token1 => true,
+            (asset, quote) if asset == token1 && quote == token0 => false,
+            _ => {
+                return Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                    "Pool {} doesn't pair {} with {}",
+                    pool_config.pool, asset.address, pool_config.quote
+                )))
+            }
+        };
+
+        let twap_window = pool_config.twap_window.unwrap_or(self.config.twap_window);
+
+        self.rate_limiter.acquire().await?;
+        let pool_price = match twap_window {
+            0 => {
+                let (sqrt_price_x96, ..) = pool
+                    .slot_0()
+                    .call()
+                    .await
+                    .change_context(Error::FetchError)
+                    .attach_printable_lazy(|| {
+                        format!("Failed to read slot0 of {pool_address:?}")
+                    })?;
+
+                PoolPrice::SqrtPriceX96(sqrt_price_x96)
+            }
+            window => {
+                let (tick_cumulatives, _) = pool
+                    .observe(vec![window, 0])
+                    .call()
+                    .await
+                    .change_context(Error::FetchError)
+                    .attach_printable_lazy(|| {
+                        format!("Failed to observe {pool_address:?} over {window}s")
+                    })?;
+
+                let average_tick = average_tick(&tick_cumulatives, window).ok_or_else(|| {
+                    Report::new(Error::FetchError)
+                        .attach_printable("Unexpected observe() result length")
+                })?;
+
+                PoolPrice::Tick(average_tick)
+            }
+        };
+
+        let price_in_quote = price_in_quote(
+            pool_price,
+            asset_is_token0,
+            asset.decimals,
+            quote_price.asset.decimals,
+        );
+
+        let price = Decimal::from_f64(price_in_quote)
+            .and_then(|price| price.checked_mul(quote_price.price))
+            .ok_or_else(|| {
+                Report::new(Error::FetchError).attach_printable(format!(
+                    "Price {price_in_quote} of {} out of range",
+                    asset.symbol
+                ))
+            })?;
+
+        Ok(Some(AssetPriceEvent {
+            provider: AssetPriceProvider::UniswapV3,
+            asset: asset.clone(),
+            price,
+            fetched_at: Utc::now(),
+            metadata: PriceMetadata::default(),
+        }))
+    }
+}
+
+#[async_trait]
+impl PollingProvider for UniswapV3Provider {
+    const NAME: &'static str = "uniswap_v3";
+
+    fn state(&self) -> &PollingState {
+        &self.state
+    }
+
+    async fn prepare(&self, asset: &Asset) -> Result<bool, Error> {
+        if asset.providers.uniswap_v3.is_none() {
+            info!(
+                "Asset has no Uniswap V3 pool, skipping in UniswapV3Provider: {}",
+                asset.symbol
+            );
+            return Ok(false);
+        }
+
+        if self.clients.get(&asset.chain).is_none() {
+            return Err(Report::new(Error::InvalidConfig).attach_printable(format!(
+                "No RPC url configured for {}, can't price {} with Uniswap V3",
+                asset.chain, asset.symbol
+            )));
+        }
+
+        Ok(true)
+    }
+
+    async fn fetch(&self) -> Result<Vec<AssetPriceEvent>, Error> {
+        self.fetch_asset_prices().await
+    }
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    value
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 2f64.powi(64) + *limb as f64)
+}
+
+/// Raw price of a pool, read from `slot0` or averaged by `observe`
+#[derive(Debug, Clone, Copy)]
+enum PoolPrice {
+    /// Square root of the price of token0 in token1, as a Q64.96 number
+    SqrtPriceX96(U256),
+    /// Tick of the price of token0 in token1, `1.0001^tick`
+    Tick(f64),
+}
+
+/// Average tick over a TWAP window from the `observe` tick cumulatives of
+/// `window` seconds ago and now
+fn average_tick(tick_cumulatives: &[i64], window: u32) -> Option<f64> {
+    let [start, end] = tick_cumulatives[..] else {
+        return None;
+    };
+
+    Some((end - start) as f64 / window as f64)
+}
+
+/// Price of the asset in quote asset units, adjusting the raw price of token0
+/// in token1 for the token order and decimals of the pool
+fn price_in_quote(
+    pool_price: PoolPrice,
+    asset_is_token0: bool,
+    asset_decimals: u8,
+    quote_decimals: u8,
+) -> f64 {
+    let raw_price = match pool_price {
+        PoolPrice::SqrtPriceX96(sqrt_price_x96) => {
+            let sqrt_price = u256_to_f64(sqrt_price_x96) / 2f64.powi(96);
+            sqrt_price * sqrt_price
+        }
+        PoolPrice::Tick(tick) => 1.0001f64.powf(tick),
+    };
+
+    let raw_price = match asset_is_token0 {
+        true => raw_price,
+        false => 1.0 / raw_price,
+    };
+
+    let decimals_delta = asset_decimals as i32 - quote_decimals as i32;
+    raw_price * 10f64.powi(decimals_delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// sqrtPriceX96 of a USDC (6 decimals, token0) / WETH (18 decimals, token1)
+    /// pool with ETH at 2000 USDC
+    const USDC_WETH_SQRT_PRICE_X96: &str = "1771595571142957102961017161607260";
+
+    /// sqrtPriceX96 of a WBTC (8 decimals, token0) / WETH (18 decimals, token1)
+    /// pool with BTC at 20 ETH
+    const WBTC_WETH_SQRT_PRICE_X96: &str = "35431911422859142059220343232145201";
+
+    fn assert_close(actual: f64, expected: f64) {
+        let error = ((actual - expected) / expected).abs();
+        assert!(error < 1e-4, "{actual} is not close to {expected}");
+    }
+
+    fn sqrt_price(value: &str) -> PoolPrice {
+        PoolPrice::SqrtPriceX96(U256::from_dec_str(value).unwrap())
+    }
+
+    #[test]
+    fn spot_price_in_both_token_orders() {
+        let pool_price = sqrt_price(USDC_WETH_SQRT_PRICE_X96);
+
+        // WETH is token1, priced in USDC
+        assert_close(price_in_quote(pool_price, false, 18, 6), 2000.0);
+        // USDC is token0, priced in WETH
+        assert_close(price_in_quote(pool_price, true, 6, 18), 0.0005);
+    }
+
+    #[test]
+    fn spot_price_with_other_decimals() {
+        let pool_price = sqrt_price(WBTC_WETH_SQRT_PRICE_X96);
+
+        assert_close(price_in_quote(pool_price, true, 8, 18), 20.0);
+        assert_close(price_in_quote(pool_price, false, 18, 8), 0.05);
+    }
+
+    #[test]
+    fn twap_price_from_the_average_tick() {
+        // Tick 200311.2 is the USDC/WETH price above, held for 10 minutes
+        let start = -1_000_000_i64;
+        let tick_cumulatives = [start, start + 200_311 * 600 + 120];
+        let tick = average_tick(&tick_cumulatives, 600).unwrap();
+        assert_close(tick, 200_311.2);
+
+        assert_close(price_in_quote(PoolPrice::Tick(tick), false, 18, 6), 2000.0);
+        assert_close(price_in_quote(PoolPrice::Tick(tick), true, 6, 18), 0.0005);
+
+        // WETH (token0) / WBTC (token1) pools have a negative tick
+        let tick = average_tick(&[0, -260_228 * 60 - 50], 60).unwrap();
+        assert_close(price_in_quote(PoolPrice::Tick(tick), false, 8, 18), 20.0);
+    }
+
+    #[test]
+    fn average_tick_needs_two_cumulatives() {
+        assert_eq!(average_tick(&[0], 600), None);
+        assert_eq!(average_tick(&[0, 1, 2], 600), None);
+    }
+}
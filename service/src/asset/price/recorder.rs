@@ -0,0 +1,73 @@
+use super::price_provider::AssetPriceEvent;
+use crate::config::RecorderConfig;
+use chrono::{DateTime, Utc};
+use error_stack::{Result, ResultExt};
+use futures::{Stream, StreamExt};
+use lib::error::Error;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// Line of a recording, read back by the replay provider
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// When the event was received, which the replay is paced by since
+    /// `fetched_at` is the time of the price at the source
+    pub recorded_at: DateTime<Utc>,
+    pub event: AssetPriceEvent,
+}
+
+/// Append every event of `events` to the recorder file as a `RecordedEvent`
+/// JSON line. `events` should not include replayed events, or a replay of the
+/// file would append its own events to it.
+pub fn spawn(
+    config: RecorderConfig,
+    mut events: Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>>,
+) -> JoinHandle<Result<(), Error>> {
+    tokio::spawn(async move {
+        let path = config.path;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .change_context(Error::Unknown)
+            .attach_printable_lazy(|| format!("Failed to open price recording {path}"))
+            .inspect_err(|e| error!("Price recorder stopped: {e:?}"))?;
+
+        let mut writer = BufWriter::new(file);
+        info!("Recording price events to {path}");
+
+        while let Some(event) = events.next().await {
+            let recorded = RecordedEvent {
+                recorded_at: Utc::now(),
+                event,
+            };
+
+            let mut line = match serde_json::to_vec(&recorded) {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("Failed to serialize price event: {e}");
+                    continue;
+                }
+            };
+            line.push(b'\n');
+
+            // Flush every line so the recording is complete if the process is killed
+            let written = async {
+                writer.write_all(&line).await?;
+                writer.flush().await
+            };
+
+            if let Err(e) = written.await {
+                error!("Failed to record price event to {path}: {e}");
+            }
+        }
+
+        Ok(())
+    })
+}
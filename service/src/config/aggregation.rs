@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::asset::Chain;
+
+/// How the latest quotes of every provider are combined into one price per asset
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AggregationConfig {
+    pub method: AggregationMethod,
+    /// Seconds after which a provider's latest quote no longer contributes
+    pub max_age: u64,
+    /// Relative deviation from the median above which a quote is an outlier,
+    /// e.g. `0.05` for 5%
+    pub max_deviation: f64,
+    /// Quotes needed once outliers are dropped to publish a price
+    pub min_sources: usize,
+    /// Weight of each provider in `weighted_mean`, by name (`pyth`, `chainlink`,
+    /// the name of a `[[providers.http]]` instance...). Defaults to 1
+    pub weights: HashMap<String, f64>,
+    /// Providers by decreasing priority for `priority`, unless set for the
+    /// asset chain in `chain_priority` or for the asset in `providers.priority`.
+    /// Unlisted providers come last
+    pub priority: Vec<String>,
+    pub chain_priority: HashMap<Chain, Vec<String>>,
+    /// Seconds without a new quote after which `priority` fails over to the
+    /// next provider, `max_age` when not set
+    pub failover_after: Option<u64>,
+    /// Only publish prices agreed on by enough providers
+    pub quorum: Option<QuorumConfig>,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            method: AggregationMethod::Median,
+            max_age: 60,
+            max_deviation: 0.05,
+            min_sources: 1,
+            weights: HashMap::new(),
+            priority: Vec::new(),
+            chain_priority: HashMap::new(),
+            failover_after: None,
+            quorum: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationMethod {
+    Median,
+    WeightedMean,
+    /// Price of the highest priority provider with a fresh quote, failing over
+    /// to the next one while it is stale or its circuit is open or failing
+    Priority,
+}
+
+/// Prices are published when at least `min_providers` fresh quotes agree
+/// within `tolerance`, replacing the outlier rejection of `max_deviation`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct QuorumConfig {
+    pub min_providers: usize,
+    /// Providers taking part in the quorum, by name. Every provider when empty
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// Relative spread between the lowest and highest agreeing quotes, e.g.
+    /// `0.01` for 1%
+    pub tolerance: f64,
+    /// Seconds after which a quote no longer counts towards the quorum,
+    /// `aggregation.max_age` when not set
+    pub max_age: Option<u64>,
+}
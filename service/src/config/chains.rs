@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::asset::Chain;
+
+/// Built-in slugs of well known chains: chain, DefiLlama chain, CoinGecko asset platform
+const BUILTIN_SLUGS: &[(&str, &str, &str)] = &[
+    ("evm:1", "ethereum", "ethereum"),
+    ("evm:10", "optimism", "optimistic-ethereum"),
+    ("evm:56", "bsc", "binance-smart-chain"),
+    ("evm:100", "xdai", "xdai"),
+    ("evm:137", "polygon", "polygon-pos"),
+    ("evm:250", "fantom", "fantom"),
+    ("evm:324", "era", "zksync"),
+    ("evm:1101", "polygon_zkevm", "polygon-zkevm"),
+    ("evm:5000", "mantle", "mantle"),
+    ("evm:8453", "base", "base"),
+    ("evm:42161", "arbitrum", "arbitrum-one"),
+    ("evm:42220", "celo", "celo"),
+    ("evm:43114", "avax", "avalanche"),
+    ("evm:59144", "linea", "linea"),
+    ("evm:81457", "blast", "blast"),
+    ("evm:534352", "scroll", "scroll"),
+    ("svm:1", "solana", "solana"),
+    ("cosmos:cosmoshub-4", "cosmos", "cosmos"),
+    ("cosmos:osmosis-1", "osmosis", "osmosis"),
+    ("aptos:mainnet", "aptos", "aptos"),
+    ("sui:mainnet", "sui", "sui"),
+    ("ton:mainnet", "ton", "the-open-network"),
+    ("tron:mainnet", "tron", "tron"),
+];
+
+/// Names of a chain in the APIs of the providers, under `[chains."<chain>"]`
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ChainSlugs {
+    /// DefiLlama chain prefix of coin identifiers, e.g. `arbitrum`
+    pub defillama: Option<String>,
+    /// CoinGecko asset platform id, e.g. `arbitrum-one`
+    pub coingecko: Option<String>,
+    /// Value of `{chain}` by `[[providers.http]]` instance name, the chain
+    /// itself (`evm:42161`) by default
+    pub http: HashMap<String, String>,
+}
+
+/// Slugs of every known chain, the built-in ones extended and overridden by
+/// the `[chains]` config
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: HashMap<Chain, ChainSlugs>,
+}
+
+impl ChainRegistry {
+    pub fn new(overrides: &HashMap<Chain, ChainSlugs>) -> Self {
+        let mut chains: HashMap<Chain, ChainSlugs> = BUILTIN_SLUGS
+            .iter()
+            .filter_map(|(chain, defillama, coingecko)| {
+                let chain = chain.parse::<Chain>().ok()?;
+
+                Some((
+                    chain,
+                    ChainSlugs {
+                        defillama: Some(defillama.to_string()),
+                        coingecko: Some(coingecko.to_string()),
+                        http: HashMap::new(),
+                    },
+                ))
+            })
+            .collect();
+
+        for (chain, slugs) in overrides {
+            let entry = chains.entry(chain.clone()).or_default();
+
+            if let Some(defillama) = &slugs.defillama {
+                entry.defillama = Some(defillama.clone());
+            }
+
+            if let Some(coingecko) = &slugs.coingecko {
+                entry.coingecko = Some(coingecko.clone());
+            }
+
+            entry.http.extend(slugs.http.clone());
+        }
+
+        Self { chains }
+    }
+
+    pub fn defillama(&self, chain: &Chain) -> Option<&str> {
+        self.chains.get(chain)?.defillama.as_deref()
+    }
+
+    pub fn coingecko(&self, chain: &Chain) -> Option<&str> {
+        self.chains.get(chain)?.coingecko.as_deref()
+    }
+
+    /// Name of the chain for a `[[providers.http]]` instance
+    pub fn http(&self, chain: &Chain, instance: &str) -> String {
+        self.chains
+            .get(chain)
+            .and_then(|slugs| slugs.http.get(instance))
+            .cloned()
+            .unwrap_or_else(|| chain.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(chain: &str) -> Chain {
+        chain.parse().unwrap()
+    }
+
+    #[test]
+    fn overrides_replace_builtin_slugs() {
+        let overrides = HashMap::from([(
+            chain("evm:42161"),
+            ChainSlugs {
+                coingecko: Some(String::from("arbitrum-nova")),
+                http: HashMap::from([(String::from("prices"), String::from("arb"))]),
+                ..Default::default()
+            },
+        )]);
+        let chains = ChainRegistry::new(&overrides);
+        let arbitrum = chain("evm:42161");
+
+        assert_eq!(chains.coingecko(&arbitrum), Some("arbitrum-nova"));
+        // Slugs missing from the override are kept
+        assert_eq!(chains.defillama(&arbitrum), Some("arbitrum"));
+        assert_eq!(chains.http(&arbitrum, "prices"), "arb");
+        assert_eq!(chains.http(&arbitrum, "other"), "evm:42161");
+    }
+
+    #[test]
+    fn resolves_chains_only_in_config() {
+        let overrides = HashMap::from([(
+            chain("evm:252"),
+            ChainSlugs {
+                defillama: Some(String::from("fraxtal")),
+                coingecko: Some(String::from("fraxtal")),
+                ..Default::default()
+            },
+        )]);
+        let chains = ChainRegistry::new(&overrides);
+
+        assert_eq!(chains.defillama(&chain("evm:252")), Some("fraxtal"));
+        assert_eq!(chains.coingecko(&chain("evm:252")), Some("fraxtal"));
+        assert_eq!(chains.defillama(&chain("evm:1")), Some("ethereum"));
+    }
+
+    #[test]
+    fn skips_chains_without_a_slug() {
+        let overrides = HashMap::from([(
+            chain("evm:252"),
+            ChainSlugs {
+                defillama: Some(String::from("fraxtal")),
+                ..Default::default()
+            },
+        )]);
+        let chains = ChainRegistry::new(&overrides);
+
+        assert_eq!(chains.coingecko(&chain("evm:252")), None);
+        assert_eq!(chains.defillama(&chain("evm:7777777")), None);
+        assert_eq!(chains.defillama(&chain("bitcoin:mainnet")), None);
+        assert_eq!(chains.coingecko(&chain("bitcoin:mainnet")), None);
+    }
+}
@@ -0,0 +1,335 @@
+use error_stack::{Report, Result, ResultExt};
+use lib::error::Error;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+use super::ConfigService;
+
+/// Prefix of the environment variables overriding config fields
+pub const ENV_PREFIX: &str = "SHOGUN__";
+
+/// Separator between path segments in environment variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// Paths of the tables whose keys are case sensitive, `*` matching any segment.
+/// Environment variable names are uppercase by convention, so their segments
+/// are lowercased to match field, provider and chain names, except for the keys
+/// of these tables.
+const CASE_SENSITIVE_PATHS: &[&str] = &["providers.http.*.headers"];
+
+/// Loads the config by layering, from lowest to highest precedence:
+///
+/// 1. Defaults of every config section
+/// 2. The TOML config file, when there is one
+/// 3. `SHOGUN__*` environment variables, e.g. `SHOGUN__TASKS__FETCHER__INTERVAL=5`
+/// 4. `--set key=value` overrides, e.g. `--set tasks.fetcher.interval=5`
+///
+/// Path segments of an override address table keys or array indexes, so
+/// `SHOGUN__ASSETS__0__SYMBOL` and `assets.0.symbol` both target the first asset.
+/// Environment variable segments are lowercased, provider and chain names
+/// included, except for HTTP header names (`SHOGUN__PROVIDERS__HTTP__0__HEADERS__X-Api-Key`).
+/// Provider names are validated lowercase so that they can all be addressed.
+///
+/// Values overriding a string are kept as is. Other values are parsed as TOML
+/// values and fall back to plain strings, so quoting (`"2024"`) forces a string.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    path: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+}
+
+#[buildstructor::buildstructor]
+impl ConfigLoader {
+    #[builder]
+    pub fn new(
+        path: Option<PathBuf>,
+        env: Vec<(String, String)>,
+        cli_overrides: Vec<(String, String)>,
+    ) -> Self {
+        let env = env
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let path = key.strip_prefix(ENV_PREFIX)?;
+
+                Some((env_path(path), value))
+            })
+            .collect();
+
+        Self {
+            path,
+            env,
+            overrides: cli_overrides,
+        }
+    }
+}
+
+impl ConfigLoader {
+    /// Config file path, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn load(&self) -> Result<ConfigService, Error> {
+        let config = match &self.path {
+            Some(path) => fs::read_to_string(path)
+                .change_context(Error::ReadConfig)
+                .attach_printable_lazy(|| format!("Failed to read {}", path.display()))?
+                .parse::<Table>()
+                .change_context(Error::InvalidConfig)
+                .attach_printable_lazy(|| format!("Failed to parse {}", path.display()))?,
+            None => Table::new(),
+        };
+        let mut config = Value::Table(config);
+
+        for (key, value) in self.env.iter().chain(self.overrides.iter()) {
+            apply_override(&mut config, key, value)?;
+        }
+
+        let config = ConfigService::deserialize(config).change_context(Error::InvalidConfig)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Set the value at the dotted `key` path, creating intermediate tables and arrays as needed
+fn apply_override(config: &mut Value, key: &str, value: &str) -> Result<(), Error> {
+    let invalid_key = || {
+        Report::new(Error::InvalidConfig).attach_printable(format!("Invalid override key `{key}`"))
+    };
+
+    let segments = key.split('.').collect::<Vec<_>>();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(invalid_key());
+    }
+
+    let mut current = config;
+    for (index, segment) in segments.iter().enumerate() {
+        let is_array = segments
+            .get(index + 1)
+            .is_some_and(|next| next.parse::<usize>().is_ok());
+
+        current = child_mut(current, segment, is_array).ok_or_else(invalid_key)?;
+    }
+
+    *current = match current {
+        Value::String(_) => parse_string(value),
+        _ => parse_value(value),
+    };
+
+    Ok(())
+}
+
+/// Dotted path of an environment variable name without its prefix, lowercasing
+/// every segment but the keys of `CASE_SENSITIVE_PATHS` tables
+fn env_path(name: &str) -> String {
+    let mut segments: Vec<String> = Vec::new();
+
+    for segment in name.split(ENV_SEPARATOR) {
+        let is_case_sensitive = CASE_SENSITIVE_PATHS.iter().any(|pattern| {
+            let pattern = pattern.split('.').collect::<Vec<_>>();
+
+            pattern.len() == segments.len()
+                && pattern
+                    .iter()
+                    .zip(&segments)
+                    .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+        });
+
+        segments.push(match is_case_sensitive {
+            true => segment.to_string(),
+            false => segment.to_lowercase(),
+        });
+    }
+
+    segments.join(".")
+}
+
+/// Get the child at `segment`, creating an empty table or array if missing
+fn child_mut<'a>(value: &'a mut Value, segment: &str, is_array: bool) -> Option<&'a mut Value> {
+    let empty = || match is_array {
+        true => Value::Array(Vec::new()),
+        false => Value::Table(Table::new()),
+    };
+
+    match value {
+        Value::Table(table) => Some(table.entry(segment.to_string()).or_insert_with(empty)),
+        Value::Array(array) => {
+            let index = segment.parse::<usize>().ok()?;
+
+            // Allow appending a new entry right after the last one
+            if index == array.len() {
+                array.push(empty());
+            }
+
+            array.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+/// Value of a string field: quoted TOML strings are unquoted, anything else is
+/// kept as is, even when it looks like a number or a boolean
+fn parse_string(raw: &str) -> Value {
+    match parse_value(raw) {
+        Value::String(string) => Value::String(string),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+fn parse_value(raw: &str) -> Value {
+    format!("value = {raw}")
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const CONFIG: &str = r#"
+        [tasks.fetcher]
+        interval = 10
+
+        [environment]
+        name = "2024"
+        otlp_grpc_endpoint = "http://localhost:4317"
+        otlp_http_endpoint = "http://localhost:4318"
+    "#;
+
+    fn config_file() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(CONFIG.as_bytes()).unwrap();
+        file
+    }
+
+    fn load(env: &[(&str, &str)], cli_overrides: &[(&str, &str)]) -> ConfigService {
+        let file = config_file();
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        ConfigLoader::builder()
+            .path(file.path().to_path_buf())
+            .env(pairs(env))
+            .cli_overrides(pairs(cli_overrides))
+            .build()
+            .load()
+            .unwrap()
+    }
+
+    #[test]
+    fn env_overrides_file_and_set_overrides_env() {
+        assert_eq!(load(&[], &[]).tasks.fetcher.interval, 10);
+
+        let env = [
+            ("SHOGUN__TASKS__FETCHER__INTERVAL", "20"),
+            ("OTHER__TASKS", "1"),
+        ];
+        assert_eq!(load(&env, &[]).tasks.fetcher.interval, 20);
+
+        let cli_overrides = [("tasks.fetcher.interval", "30")];
+        assert_eq!(load(&env, &cli_overrides).tasks.fetcher.interval, 30);
+    }
+
+    #[test]
+    fn overrides_of_strings_stay_strings() {
+        let config = load(&[("SHOGUN__ENVIRONMENT__NAME", "42")], &[]);
+        assert_eq!(config.environment.name, "42");
+
+        let config = load(&[], &[("environment.name", "\"prod\"")]);
+        assert_eq!(config.environment.name, "prod");
+    }
+
+    #[test]
+    fn env_path_keeps_the_case_of_header_names_only() {
+        assert_eq!(
+            env_path("TASKS__FETCHER__INTERVAL"),
+            "tasks.fetcher.interval"
+        );
+        assert_eq!(
+            env_path("PROVIDERS__HTTP__0__HEADERS__X-Api-Key"),
+            "providers.http.0.headers.X-Api-Key"
+        );
+        assert_eq!(
+            env_path("TASKS__FETCHER__RETRY__PROVIDERS__PYTH__MAX_ATTEMPTS"),
+            "tasks.fetcher.retry.providers.pyth.max_attempts"
+        );
+        assert_eq!(
+            env_path("AGGREGATION__WEIGHTS__CHAINLINK"),
+            "aggregation.weights.chainlink"
+        );
+        assert_eq!(
+            env_path("ASSETS__1__PROVIDERS__HTTP__MyApi"),
+            "assets.1.providers.http.myapi"
+        );
+    }
+
+    #[test]
+    fn env_overrides_of_provider_keyed_maps_are_valid() {
+        let env = [
+            ("SHOGUN__RATE_LIMITS__DEFILLAMA__REQUESTS_PER_SECOND", "2"),
+            ("SHOGUN__AGGREGATION__WEIGHTS__COINGECKO", "0.5"),
+        ];
+        let config = load(&env, &[]);
+
+        assert_eq!(config.rate_limits["defillama"].requests_per_second, 2.0);
+        assert_eq!(config.aggregation.weights["coingecko"], 0.5);
+    }
+
+    #[test]
+    fn apply_override_creates_tables_and_arrays() {
+        let mut config = Value::Table(Table::new());
+
+        apply_override(&mut config, "tasks.fetcher.interval", "5").unwrap();
+        apply_override(&mut config, "assets.0.symbol", "WETH").unwrap();
+        apply_override(&mut config, "assets.0.decimals", "18").unwrap();
+        apply_override(&mut config, "assets.1.symbol", "USDC").unwrap();
+        apply_override(&mut config, "assets.1.name", "\"2024\"").unwrap();
+        apply_override(
+            &mut config,
+            "aggregation.priority",
+            "[\"chainlink\", \"pyth\"]",
+        )
+        .unwrap();
+
+        let expected: Table = r#"
+            aggregation = { priority = ["chainlink", "pyth"] }
+            tasks = { fetcher = { interval = 5 } }
+            assets = [
+                { symbol = "WETH", decimals = 18 },
+                { symbol = "USDC", name = "2024" },
+            ]
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(config, Value::Table(expected));
+
+        // An existing string is only replaced by a string
+        apply_override(&mut config, "assets.0.symbol", "1234").unwrap();
+        assert_eq!(config["assets"][0]["symbol"], Value::String("1234".into()));
+    }
+
+    #[test]
+    fn apply_override_rejects_invalid_keys() {
+        let mut config = Value::Table(Table::new());
+        apply_override(&mut config, "assets.0.symbol", "WETH").unwrap();
+
+        // Indexes can only append right after the last entry
+        assert!(apply_override(&mut config, "assets.2.symbol", "USDC").is_err());
+        // Scalars have no children
+        assert!(apply_override(&mut config, "assets.0.symbol.inner", "x").is_err());
+        assert!(apply_override(&mut config, "tasks..interval", "5").is_err());
+    }
+}
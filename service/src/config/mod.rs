@@ -1,15 +1,40 @@
+pub mod aggregation;
+pub mod chains;
+pub mod loader;
+pub mod providers;
+pub mod validation;
+pub mod watcher;
+
 use async_trait::async_trait;
 use error_stack::{Report, Result, ResultExt};
 use lib::error::Error;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, fs, ops::Deref, path::Path, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap, fmt::Display, fs, ops::Deref, path::Path, str::FromStr, sync::Arc,
+};
+
+use aggregation::AggregationConfig;
+use chains::ChainSlugs;
+use providers::ProvidersConfig;
 
-use crate::services::{ServiceFactory, ServiceProvider};
+use crate::{
+    asset::{Asset, Chain},
+    services::{ServiceFactory, ServiceProvider},
+};
 
 #[derive(Debug, Serialize)]
 pub struct ConfigServiceInner {
     pub tasks: TaskConfigs,
     pub environment: EnvironmentConfig,
+    pub assets: Vec<Asset>,
+    pub providers: ProvidersConfig,
+    pub aggregation: AggregationConfig,
+    pub health: HealthConfig,
+    /// Request limits by provider name, providers without one are not limited
+    pub rate_limits: HashMap<String, RateLimitConfig>,
+    /// Provider slugs of chains, extending and overriding the built-in ones
+    pub chains: HashMap<Chain, ChainSlugs>,
+    pub rpc: Vec<RpcConfig>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -24,6 +49,20 @@ impl<'de> Deserialize<'de> for ConfigService {
         struct AdHocConfig {
             pub tasks: TaskConfigs,
             pub environment: EnvironmentConfig,
+            #[serde(default)]
+            pub assets: Vec<Asset>,
+            #[serde(default)]
+            pub providers: ProvidersConfig,
+            #[serde(default)]
+            pub aggregation: AggregationConfig,
+            #[serde(default)]
+            pub health: HealthConfig,
+            #[serde(default)]
+            pub rate_limits: HashMap<String, RateLimitConfig>,
+            #[serde(default)]
+            pub chains: HashMap<Chain, ChainSlugs>,
+            #[serde(default)]
+            pub rpc: Vec<RpcConfig>,
         }
 
         let ad_hoc: AdHocConfig = serde::Deserialize::deserialize(deserializer)?;
@@ -31,6 +70,13 @@ impl<'de> Deserialize<'de> for ConfigService {
         ConfigService::builder()
             .tasks(ad_hoc.tasks)
             .environment(ad_hoc.environment)
+            .assets(ad_hoc.assets)
+            .providers(ad_hoc.providers)
+            .aggregation(ad_hoc.aggregation)
+            .health(ad_hoc.health)
+            .rate_limits(ad_hoc.rate_limits)
+            .chains(ad_hoc.chains)
+            .rpc(ad_hoc.rpc)
             .build()
             .map_err(|e| serde::de::Error::custom(e.to_string()))
     }
@@ -41,6 +87,7 @@ impl FromStr for ConfigService {
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         let toml_config: ConfigService = toml::from_str(s).change_context(Error::InvalidConfig)?;
+        toml_config.validate()?;
 
         Ok(toml_config)
     }
@@ -64,6 +111,12 @@ impl ConfigService {
     pub fn inner(&self) -> Arc<ConfigServiceInner> {
         self.0.clone()
     }
+
+    /// Validate the config, reporting every problem at once as
+    /// `validation::ConfigProblem` attachments
+    pub fn validate(&self) -> Result<(), Error> {
+        validation::validate(&self.0)
+    }
 }
 
 #[buildstructor::buildstructor]
@@ -72,17 +125,33 @@ impl ConfigService {
     pub fn new(
         tasks: Option<TaskConfigs>,
         environment: Option<EnvironmentConfig>,
+        assets: Vec<Asset>,
+        providers: Option<ProvidersConfig>,
+        aggregation: Option<AggregationConfig>,
+        health: Option<HealthConfig>,
+        rate_limits: HashMap<String, RateLimitConfig>,
+        chains: HashMap<Chain, ChainSlugs>,
+        rpc: Vec<RpcConfig>,
     ) -> Result<Self, Error> {
         let inner = ConfigServiceInner {
             tasks: tasks.unwrap_or_default(),
             environment: environment.unwrap_or_default(),
+            assets,
+            providers: providers.unwrap_or_default(),
+            aggregation: aggregation.unwrap_or_default(),
+            health: health.unwrap_or_default(),
+            rate_limits,
+            chains,
+            rpc,
         };
 
         Ok(ConfigService(Arc::new(inner)))
     }
 
     pub fn read_file(path: &Path) -> Result<Self, Error> {
-        let config = fs::read_to_string(path).change_context(Error::Unknown)?;
+        let config = fs::read_to_string(path)
+            .change_context(Error::ReadConfig)
+            .attach_printable_lazy(|| format!("Failed to read {}", path.display()))?;
 
         config.parse()
     }
@@ -91,11 +160,104 @@ impl ConfigService {
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TaskConfigs {
     pub fetcher: TaskConfig,
+    /// Record every price event, to replay it later through `[providers.replay]`
+    #[serde(default)]
+    pub recorder: Option<RecorderConfig>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct TaskConfig {
     pub interval: u64,
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
+
+/// Retry policy of polling providers, with overrides by provider name
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RetryConfig {
+    #[serde(flatten)]
+    pub default: RetryPolicy,
+    /// Policies replacing the default one, by provider name (`coingecko`, the
+    /// name of a `[[providers.http]]` instance...)
+    pub providers: HashMap<String, RetryPolicy>,
+}
+
+impl RetryConfig {
+    pub fn policy(&self, provider: &str) -> RetryPolicy {
+        self.providers
+            .get(provider)
+            .unwrap_or(&self.default)
+            .clone()
+    }
+}
+
+/// How a polling provider retries a fetch failing with a transient error
+/// (timeout, connection error, 5xx, 429) before waiting for the next tick
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Fetch attempts per tick, the first one included
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every following one
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Share of every delay randomly added or removed, e.g. `0.2` for ±20%
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            jitter: 0.2,
+        }
+    }
+}
+
+/// Thresholds of the provider health state machine and circuit breaker
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Consecutive failures opening the circuit of a provider
+    pub failure_threshold: u32,
+    /// Seconds an open circuit skips calls before probing the provider again
+    pub open_secs: u64,
+    /// Successful calls slower than this degrade a provider
+    pub slow_ms: u64,
+    /// Prices older than this many seconds degrade the provider returning them
+    pub stale_after: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_secs: 30,
+            slow_ms: 5_000,
+            stale_after: 3_600,
+        }
+    }
+}
+
+/// Token bucket limiting the HTTP requests or RPC calls of a provider
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Sustained request rate, e.g. `0.5` for one request every 2 seconds
+    pub requests_per_second: f64,
+    /// Requests that can be sent at once after being idle, `requests_per_second`
+    /// rounded up when not set
+    pub burst: Option<u32>,
+    /// Requests allowed per UTC day
+    pub daily_quota: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RecorderConfig {
+    /// JSONL file events are appended to
+    pub path: String,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -105,6 +267,13 @@ pub struct EnvironmentConfig {
     pub otlp_http_endpoint: String,
 }
 
+/// JSON-RPC endpoint of an EVM chain
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RpcConfig {
+    pub chain_id: u64,
+    pub url: String,
+}
+
 #[async_trait]
 impl ServiceFactory for ConfigService {
     async fn factory(_services: ServiceProvider) -> Result<Self, Error> {
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Price providers settings. A provider other than DefiLlama is only enabled
+/// when its section is present.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProvidersConfig {
+    pub defillama: DefiLlamaConfig,
+    pub coingecko: Option<CoinGeckoConfig>,
+    pub pyth: Option<PythConfig>,
+    pub chainlink: Option<ChainlinkConfig>,
+    pub uniswap_v3: Option<UniswapV3Config>,
+    pub uniswap_v2: Option<UniswapV2Config>,
+    pub jupiter: Option<JupiterConfig>,
+    pub binance: Option<BinanceConfig>,
+    pub coinbase: Option<CoinbaseConfig>,
+    /// Generic HTTP/JSON sources, one provider per entry
+    pub http: Vec<HttpProviderConfig>,
+    pub mock: Option<MockConfig>,
+    pub replay: Option<ReplayConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct DefiLlamaConfig {
+    /// DefiLlama is enabled unless turned off, e.g. to run offline
+    pub enabled: bool,
+    pub base_url: String,
+    /// Maximum number of coins per request, as they are all part of the URL path
+    pub batch_size: usize,
+    /// Maximum number of requests in flight
+    pub max_concurrency: usize,
+}
+
+impl Default for DefiLlamaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_url: String::from("https://coins.llama.fi"),
+            batch_size: 50,
+            max_concurrency: 4,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct CoinGeckoConfig {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    /// Header carrying the API key, `x-cg-pro-api-key` for the pro API
+    pub api_key_header: String,
+    /// Maximum number of contract addresses per request
+    pub batch_size: usize,
+}
+
+impl Default for CoinGeckoConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::from("https://api.coingecko.com/api/v3"),
+            api_key: None,
+            api_key_header: String::from("x-cg-demo-api-key"),
+            batch_size: 50,
+        }
+    }
+}
+
+/// Maximum number of mint addresses Jupiter prices per request
+pub const JUPITER_MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct JupiterConfig {
+    /// Jupiter API url, `https://api.jup.ag` when using an API key
+    pub base_url: String,
+    /// Sent as the `x-api-key` header
+    pub api_key: Option<String>,
+    /// Maximum number of mint addresses per request, up to `JUPITER_MAX_BATCH_SIZE`
+    pub batch_size: usize,
+}
+
+impl Default for JupiterConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::from("https://lite-api.jup.ag"),
+            api_key: None,
+            batch_size: JUPITER_MAX_BATCH_SIZE,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct PythConfig {
+    /// Hermes API url
+    pub base_url: String,
+    pub mode: PythMode,
+    /// Seconds without data after which a stalled stream is reconnected
+    pub stream_idle_secs: u64,
+}
+
+impl Default for PythConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::from("https://hermes.pyth.network"),
+            mode: PythMode::default(),
+            stream_idle_secs: 30,
+        }
+    }
+}
+
+/// How Pyth prices are received
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PythMode {
+    /// Fetch the latest prices every `tasks.fetcher.interval`
+    #[default]
+    Poll,
+    /// Receive every price update through Hermes server-sent events
+    Stream,
+}
+
+/// Chainlink aggregators are set per asset through `providers.chainlink`, and
+/// read through the `[[rpc]]` endpoint of the asset chain.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ChainlinkConfig {}
+
+/// Uniswap V3 pools are set per asset through `providers.uniswap_v3`, and read
+/// through the `[[rpc]]` endpoint of the asset chain.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct UniswapV3Config {
+    /// Default TWAP window in seconds, `0` uses the spot price
+    pub twap_window: u32,
+    /// Maximum age in seconds of the aggregated quote asset price, older
+    /// prices skip the asset
+    pub quote_max_age: u64,
+}
+
+impl Default for UniswapV3Config {
+    fn default() -> Self {
+        Self {
+            twap_window: 0,
+            quote_max_age: 60,
+        }
+    }
+}
+
+/// Uniswap V2-compatible pairs (Sushi, PancakeSwap, ...) are set per asset
+/// through `providers.uniswap_v2`, and read through the `[[rpc]]` endpoint of
+/// the asset chain.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct UniswapV2Config {
+    /// Maximum age in seconds of the aggregated quote asset price, older
+    /// prices skip the asset
+    pub quote_max_age: u64,
+}
+
+impl Default for UniswapV2Config {
+    fn default() -> Self {
+        Self { quote_max_age: 60 }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct BinanceConfig {
+    /// Binance WebSocket streams url
+    pub base_url: String,
+    pub stream: BinanceStream,
+    /// Minimum delay in milliseconds between two events of an asset, `0` emits every tick
+    pub throttle_ms: u64,
+}
+
+impl Default for BinanceConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::from("wss://stream.binance.com:9443"),
+            stream: BinanceStream::default(),
+            throttle_ms: 0,
+        }
+    }
+}
+
+/// Binance stream prices are read from
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinanceStream {
+    /// Mid price of the best bid and ask, on every order book update
+    #[default]
+    BookTicker,
+    /// Last price, every second
+    MiniTicker,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct CoinbaseConfig {
+    /// Advanced Trade WebSocket url
+    pub ws_url: String,
+    /// Advanced Trade REST API url, used for snapshots when ticker messages are missed
+    pub rest_url: String,
+}
+
+impl Default for CoinbaseConfig {
+    fn default() -> Self {
+        Self {
+            ws_url: String::from("wss://advanced-trade-ws.coinbase.com"),
+            rest_url: String::from("https://api.coinbase.com"),
+        }
+    }
+}
+
+/// A JSON price API described entirely in config.
+///
+/// `url` and the pointers can use `{id}`, `{address}`, `{symbol}` and `{chain}`,
+/// replaced by the values of each asset. `{id}` is the asset
+/// `providers.http.<name>` id, or its address when not set. Batched requests
+/// use `{ids}` in `url` for the ids of every asset in the batch.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HttpProviderConfig {
+    /// Name of the instance, used in logs and by assets to set their id
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Maximum number of assets per request, `1` requests every asset on its own
+    #[serde(default = "default_http_batch_size")]
+    pub batch_size: usize,
+    /// Separator of the ids replacing `{ids}`
+    #[serde(default = "default_http_ids_separator")]
+    pub ids_separator: String,
+    /// JSON pointer to the price of an asset in the response, e.g. `/data/{id}/price`
+    pub price_pointer: String,
+    /// JSON pointer to the price timestamp, in seconds, milliseconds or RFC 3339.
+    /// The fetch time is used when not set
+    pub timestamp_pointer: Option<String>,
+}
+
+fn default_http_batch_size() -> usize {
+    1
+}
+
+fn default_http_ids_separator() -> String {
+    String::from(",")
+}
+
+/// Mock prices are set per asset through `providers.mock`
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct MockConfig {
+    /// Seed of the random walks, the same seed yields the same prices
+    pub seed: u64,
+}
+
+/// Plays back price events recorded by `[tasks.recorder]`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReplayConfig {
+    /// JSONL file of recorded events
+    pub path: String,
+    /// Playback speed relative to the recording, `0` replays as fast as possible
+    #[serde(default = "default_replay_speed")]
+    pub speed: f64,
+    /// Shift event timestamps to the replay time, keeping the age prices had
+    /// when recorded, instead of keeping the recorded timestamps
+    #[serde(default = "default_replay_shift_timestamps")]
+    pub shift_timestamps: bool,
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_replay_shift_timestamps() -> bool {
+    true
+}
@@ -0,0 +1,1011 @@
+use error_stack::{Report, Result};
+use lib::error::Error;
+use reqwest::Url;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Display,
+};
+
+use super::providers::JUPITER_MAX_BATCH_SIZE;
+use super::{ConfigServiceInner, RetryPolicy};
+use crate::asset::{address::validate_evm_address, Asset, AssetId, Chain, MockPrice};
+
+/// Names of the built-in providers, shared with `[[providers.http]]` instance
+/// names when referring to a provider (see `AssetPriceProvider`'s `Display`)
+const PROVIDER_NAMES: &[&str] = &[
+    "defillama",
+    "coingecko",
+    "pyth",
+    "chainlink",
+    "uniswap_v3",
+    "uniswap_v2",
+    "jupiter",
+    "binance",
+    "coinbase",
+    "mock",
+];
+
+/// Built-in providers sending no request `rate_limits` could limit, the Binance
+/// stream being a single long lived connection and mock prices coming from config
+const UNLIMITED_PROVIDERS: &[&str] = &["binance", "mock"];
+
+/// A single config problem, attached to the `Error::InvalidConfig` report
+#[derive(Debug, Clone)]
+pub struct ConfigProblem {
+    /// Dotted path of the offending field, e.g. `assets[1].address`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Collects every config problem so they can be reported at once
+#[derive(Default)]
+struct Problems(Vec<ConfigProblem>);
+
+impl Problems {
+    fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.0.push(ConfigProblem {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn into_result(self) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let count = self.0.len();
+        let report = self
+            .0
+            .into_iter()
+            .fold(Report::new(Error::InvalidConfig), |report, problem| {
+                report.attach_printable(problem)
+            });
+
+        Err(report.attach_printable(format!("Found {count} config problem(s)")))
+    }
+}
+
+/// Validate the whole config, reporting every problem found
+pub fn validate(config: &ConfigServiceInner) -> Result<(), Error> {
+    let mut problems = Problems::default();
+
+    validate_environment(&mut problems, config);
+    let http_names = validate_providers(&mut problems, config);
+
+    let is_provider = |name: &str| PROVIDER_NAMES.contains(&name) || http_names.contains_key(name);
+
+    validate_tasks(&mut problems, config, is_provider);
+    validate_health(&mut problems, config);
+    validate_rate_limits(&mut problems, config, is_provider);
+    validate_chains(&mut problems, config, &http_names);
+    validate_aggregation(&mut problems, config, is_provider);
+    let rpc_chains = validate_rpc(&mut problems, config);
+    validate_assets(&mut problems, config, &http_names, &rpc_chains, is_provider);
+
+    problems.into_result()
+}
+
+fn validate_environment(problems: &mut Problems, config: &ConfigServiceInner) {
+    validate_url(
+        problems,
+        "environment.otlp_grpc_endpoint",
+        &config.environment.otlp_grpc_endpoint,
+    );
+    validate_url(
+        problems,
+        "environment.otlp_http_endpoint",
+        &config.environment.otlp_http_endpoint,
+    );
+}
+
+/// Validate the `[providers]` section, returning the index of every
+/// `[[providers.http]]` instance by name
+fn validate_providers<'a>(
+    problems: &mut Problems,
+    config: &'a ConfigServiceInner,
+) -> HashMap<&'a str, usize> {
+    let defillama = &config.providers.defillama;
+    validate_url(
+        problems,
+        "providers.defillama.base_url",
+        &defillama.base_url,
+    );
+
+    if defillama.batch_size == 0 {
+        problems.push("providers.defillama.batch_size", "must be greater than 0");
+    }
+
+    if defillama.max_concurrency == 0 {
+        problems.push("providers.defillama.max_concurrency", "must be at least 1");
+    }
+
+    if let Some(coingecko) = &config.providers.coingecko {
+        validate_url(
+            problems,
+            "providers.coingecko.base_url",
+            &coingecko.base_url,
+        );
+
+        if coingecko.batch_size == 0 {
+            problems.push("providers.coingecko.batch_size", "must be greater than 0");
+        }
+    }
+
+    if let Some(jupiter) = &config.providers.jupiter {
+        validate_url(problems, "providers.jupiter.base_url", &jupiter.base_url);
+
+        if jupiter.batch_size == 0 {
+            problems.push("providers.jupiter.batch_size", "must be greater than 0");
+        } else if jupiter.batch_size > JUPITER_MAX_BATCH_SIZE {
+            problems.push(
+                "providers.jupiter.batch_size",
+                format!("must be at most {JUPITER_MAX_BATCH_SIZE}, Jupiter's limit"),
+            );
+        }
+    }
+
+    if let Some(binance) = &config.providers.binance {
+        validate_url_scheme(
+            problems,
+            "providers.binance.base_url",
+            &binance.base_url,
+            &["ws", "wss"],
+        );
+    }
+
+    if let Some(coinbase) = &config.providers.coinbase {
+        validate_url_scheme(
+            problems,
+            "providers.coinbase.ws_url",
+            &coinbase.ws_url,
+            &["ws", "wss"],
+        );
+        validate_url(problems, "providers.coinbase.rest_url", &coinbase.rest_url);
+    }
+
+    if let Some(pyth) = &config.providers.pyth {
+        validate_url(problems, "providers.pyth.base_url", &pyth.base_url);
+
+        if pyth.stream_idle_secs == 0 {
+            problems.push("providers.pyth.stream_idle_secs", "must be greater than 0");
+        }
+    }
+
+    if let Some(uniswap_v3) = &config.providers.uniswap_v3 {
+        if uniswap_v3.quote_max_age == 0 {
+            problems.push(
+                "providers.uniswap_v3.quote_max_age",
+                "must be greater than 0 seconds",
+            );
+        }
+    }
+
+    if let Some(uniswap_v2) = &config.providers.uniswap_v2 {
+        if uniswap_v2.quote_max_age == 0 {
+            problems.push(
+                "providers.uniswap_v2.quote_max_age",
+                "must be greater than 0 seconds",
+            );
+        }
+    }
+
+    if let Some(replay) = &config.providers.replay {
+        if replay.path.trim().is_empty() {
+            problems.push("providers.replay.path", "must not be empty");
+        }
+
+        if !(replay.speed >= 0.0 && replay.speed.is_finite()) {
+            problems.push("providers.replay.speed", "must be 0 or a positive number");
+        }
+    }
+
+    validate_http_providers(problems, config)
+}
+
+fn validate_http_providers<'a>(
+    problems: &mut Problems,
+    config: &'a ConfigServiceInner,
+) -> HashMap<&'a str, usize> {
+    let mut http_names: HashMap<&str, usize> = HashMap::new();
+
+    for (index, http) in config.providers.http.iter().enumerate() {
+        let path = format!("providers.http[{index}]");
+
+        // Environment variables lowercase the provider names they key maps by
+        if http.name != http.name.to_lowercase() {
+            problems.push(
+                format!("{path}.name"),
+                format!(
+                    "`{}` must be lowercase, as environment variables address providers in lowercase",
+                    http.name
+                ),
+            );
+        }
+
+        if http.name.trim().is_empty() {
+            problems.push(format!("{path}.name"), "must not be empty");
+        } else if PROVIDER_NAMES.contains(&http.name.as_str()) {
+            problems.push(
+                format!("{path}.name"),
+                format!("`{}` is the name of a built-in provider", http.name),
+            );
+        } else if let Some(first) = http_names.insert(&http.name, index) {
+            problems.push(
+                format!("{path}.name"),
+                format!(
+                    "duplicates providers.http[{first}], `{}` is already used",
+                    http.name
+                ),
+            );
+        }
+
+        validate_url(problems, &format!("{path}.url"), &http.url);
+
+        if http.batch_size == 0 {
+            problems.push(format!("{path}.batch_size"), "must be greater than 0");
+        } else if http.batch_size > 1 && !http.url.contains("{ids}") {
+            problems.push(
+                format!("{path}.url"),
+                "must contain `{ids}` when batch_size is greater than 1",
+            );
+        }
+
+        for (field, pointer) in [
+            ("price_pointer", Some(&http.price_pointer)),
+            ("timestamp_pointer", http.timestamp_pointer.as_ref()),
+        ] {
+            // An empty pointer is the whole response
+            if let Some(pointer) =
+                pointer.filter(|pointer| !pointer.is_empty() && !pointer.starts_with('/'))
+            {
+                problems.push(
+                    format!("{path}.{field}"),
+                    format!("`{pointer}` is not a JSON pointer, it must start with `/`"),
+                );
+            }
+        }
+    }
+
+    http_names
+}
+
+fn validate_tasks(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    is_provider: impl Fn(&str) -> bool,
+) {
+    if config.tasks.fetcher.interval == 0 {
+        problems.push("tasks.fetcher.interval", "must be greater than 0 seconds");
+    }
+
+    if let Some(recorder) = &config.tasks.recorder {
+        if recorder.path.trim().is_empty() {
+            problems.push("tasks.recorder.path", "must not be empty");
+        }
+    }
+
+    let retry = &config.tasks.fetcher.retry;
+    validate_retry(problems, "tasks.fetcher.retry", &retry.default);
+
+    for (provider, policy) in retry.providers.iter() {
+        let path = format!("tasks.fetcher.retry.providers.{provider}");
+
+        if !is_provider(provider) {
+            problems.push(path, format!("unknown provider `{provider}`"));
+        } else {
+            validate_retry(problems, &path, policy);
+        }
+    }
+}
+
+fn validate_health(problems: &mut Problems, config: &ConfigServiceInner) {
+    let health = &config.health;
+
+    if health.failure_threshold == 0 {
+        problems.push("health.failure_threshold", "must be at least 1");
+    }
+
+    if health.open_secs == 0 {
+        problems.push("health.open_secs", "must be greater than 0 seconds");
+    }
+
+    if health.stale_after == 0 {
+        problems.push("health.stale_after", "must be greater than 0 seconds");
+    }
+}
+
+fn validate_rate_limits(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    is_provider: impl Fn(&str) -> bool,
+) {
+    for (provider, rate_limit) in config.rate_limits.iter() {
+        let path = format!("rate_limits.{provider}");
+
+        if !is_provider(provider) {
+            problems.push(path, format!("unknown provider `{provider}`"));
+            continue;
+        }
+
+        if UNLIMITED_PROVIDERS.contains(&provider.as_str()) {
+            problems.push(path, format!("`{provider}` sends no rate limited request"));
+            continue;
+        }
+
+        if !(rate_limit.requests_per_second > 0.0 && rate_limit.requests_per_second.is_finite()) {
+            problems.push(
+                format!("{path}.requests_per_second"),
+                "must be a positive number",
+            );
+        }
+
+        if rate_limit.burst == Some(0) {
+            problems.push(format!("{path}.burst"), "must be at least 1");
+        }
+
+        if rate_limit.daily_quota == Some(0) {
+            problems.push(format!("{path}.daily_quota"), "must be at least 1");
+        }
+    }
+}
+
+fn validate_chains(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    http_names: &HashMap<&str, usize>,
+) {
+    for (chain, slugs) in config.chains.iter() {
+        let path = format!("chains.\"{chain}\"");
+
+        for (provider, slug) in [
+            ("defillama", &slugs.defillama),
+            ("coingecko", &slugs.coingecko),
+        ] {
+            if slug.as_ref().is_some_and(|slug| slug.trim().is_empty()) {
+                problems.push(format!("{path}.{provider}"), "must not be empty");
+            }
+        }
+
+        for (name, slug) in slugs.http.iter() {
+            if !http_names.contains_key(name.as_str()) {
+                problems.push(
+                    format!("{path}.http.{name}"),
+                    format!("unknown `[[providers.http]]` name `{name}`"),
+                );
+            } else if slug.trim().is_empty() {
+                problems.push(format!("{path}.http.{name}"), "must not be empty");
+            }
+        }
+    }
+}
+
+fn validate_aggregation(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    is_provider: impl Fn(&str) -> bool + Copy,
+) {
+    let aggregation = &config.aggregation;
+
+    if aggregation.max_age == 0 {
+        problems.push("aggregation.max_age", "must be greater than 0 seconds");
+    }
+
+    if !(aggregation.max_deviation > 0.0 && aggregation.max_deviation.is_finite()) {
+        problems.push("aggregation.max_deviation", "must be a positive number");
+    }
+
+    if aggregation.min_sources == 0 {
+        problems.push("aggregation.min_sources", "must be at least 1");
+    }
+
+    if aggregation.failover_after == Some(0) {
+        problems.push(
+            "aggregation.failover_after",
+            "must be greater than 0 seconds",
+        );
+    }
+
+    for (provider, weight) in aggregation.weights.iter() {
+        if !is_provider(provider) {
+            problems.push(
+                format!("aggregation.weights.{provider}"),
+                format!("unknown provider `{provider}`"),
+            );
+        } else if !(*weight > 0.0 && weight.is_finite()) {
+            problems.push(
+                format!("aggregation.weights.{provider}"),
+                "must be a positive number",
+            );
+        }
+    }
+
+    validate_provider_names(
+        problems,
+        "aggregation.priority",
+        &aggregation.priority,
+        is_provider,
+    );
+
+    if let Some(quorum) = &aggregation.quorum {
+        if quorum.min_providers == 0 {
+            problems.push("aggregation.quorum.min_providers", "must be at least 1");
+        } else if !quorum.providers.is_empty() && quorum.min_providers > quorum.providers.len() {
+            problems.push(
+                "aggregation.quorum.min_providers",
+                format!(
+                    "must be at most the {} quorum providers",
+                    quorum.providers.len()
+                ),
+            );
+        }
+
+        if quorum.max_age == Some(0) {
+            problems.push(
+                "aggregation.quorum.max_age",
+                "must be greater than 0 seconds",
+            );
+        }
+
+        if !(quorum.tolerance >= 0.0 && quorum.tolerance.is_finite()) {
+            problems.push(
+                "aggregation.quorum.tolerance",
+                "must be 0 or a positive number",
+            );
+        }
+
+        validate_provider_names(
+            problems,
+            "aggregation.quorum.providers",
+            &quorum.providers,
+            is_provider,
+        );
+    }
+
+    for (chain, priority) in aggregation.chain_priority.iter() {
+        validate_provider_names(
+            problems,
+            &format!("aggregation.chain_priority.\"{chain}\""),
+            priority,
+            is_provider,
+        );
+    }
+}
+
+/// Validate the `[[rpc]]` urls, returning the index of the url of every chain id
+fn validate_rpc(problems: &mut Problems, config: &ConfigServiceInner) -> HashMap<u64, usize> {
+    let mut rpc_chains: HashMap<u64, usize> = HashMap::new();
+
+    for (index, rpc) in config.rpc.iter().enumerate() {
+        validate_url(problems, &format!("rpc[{index}].url"), &rpc.url);
+
+        if let Some(first) = rpc_chains.insert(rpc.chain_id, index) {
+            problems.push(
+                format!("rpc[{index}].chain_id"),
+                format!(
+                    "duplicates rpc[{first}], chain {} already has an RPC url",
+                    rpc.chain_id
+                ),
+            );
+        }
+    }
+
+    rpc_chains
+}
+
+fn validate_assets(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    http_names: &HashMap<&str, usize>,
+    rpc_chains: &HashMap<u64, usize>,
+    is_provider: impl Fn(&str) -> bool + Copy,
+) {
+    // Addresses are compared case-insensitively on EVM chains, where casing is only a checksum
+    let mut seen: HashMap<AssetId, usize> = HashMap::new();
+
+    for (index, asset) in config.assets.iter().enumerate() {
+        let path = format!("assets[{index}]");
+
+        if asset.symbol.trim().is_empty() {
+            problems.push(format!("{path}.symbol"), "must not be empty");
+        }
+
+        if asset.address.trim().is_empty() {
+            problems.push(format!("{path}.address"), "must not be empty");
+            continue;
+        }
+
+        if let Err(reason) = asset.chain.validate_address(&asset.address) {
+            problems.push(format!("{path}.address"), reason);
+        }
+
+        validate_asset_providers(problems, config, asset, &path, http_names, rpc_chains);
+
+        validate_provider_names(
+            problems,
+            &format!("{path}.providers.priority"),
+            &asset.providers.priority,
+            is_provider,
+        );
+
+        match seen.entry(asset.id()) {
+            Entry::Occupied(first) => problems.push(
+                format!("{path}.address"),
+                format!(
+                    "duplicates assets[{}], {} is already declared on {}",
+                    first.get(),
+                    asset.address,
+                    asset.chain
+                ),
+            ),
+            Entry::Vacant(entry) => {
+                entry.insert(index);
+            }
+        }
+    }
+}
+
+/// Validate the `providers` of the asset at `path`
+fn validate_asset_providers(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    asset: &Asset,
+    path: &str,
+    http_names: &HashMap<&str, usize>,
+    rpc_chains: &HashMap<u64, usize>,
+) {
+    if let Some(aggregator) = &asset.providers.chainlink {
+        let aggregator_path = format!("{path}.providers.chainlink");
+
+        if let Err(reason) = validate_evm_address(aggregator) {
+            problems.push(&aggregator_path, reason);
+        }
+
+        validate_evm_rpc(
+            problems,
+            rpc_chains,
+            asset,
+            &aggregator_path,
+            config.providers.chainlink.is_some(),
+            "Chainlink aggregators",
+        );
+    }
+
+    if let Some(pool) = &asset.providers.uniswap_v3 {
+        let pool_path = format!("{path}.providers.uniswap_v3");
+
+        if let Err(reason) = validate_evm_address(&pool.pool) {
+            problems.push(format!("{pool_path}.pool"), reason);
+        }
+
+        validate_quote(problems, config, asset, &pool_path, &pool.quote);
+        validate_evm_rpc(
+            problems,
+            rpc_chains,
+            asset,
+            &pool_path,
+            config.providers.uniswap_v3.is_some(),
+            "Uniswap V3 pools",
+        );
+    }
+
+    if let Some(pair) = &asset.providers.uniswap_v2 {
+        let pair_path = format!("{path}.providers.uniswap_v2");
+
+        if let Err(reason) = validate_evm_address(&pair.pair) {
+            problems.push(format!("{pair_path}.pair"), reason);
+        }
+
+        validate_quote(problems, config, asset, &pair_path, &pair.quote);
+        validate_evm_rpc(
+            problems,
+            rpc_chains,
+            asset,
+            &pair_path,
+            config.providers.uniswap_v2.is_some(),
+            "Uniswap V2 pairs",
+        );
+    }
+
+    if let Some(feed_id) = &asset.providers.pyth {
+        let hex = feed_id.trim_start_matches("0x");
+
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            problems.push(
+                format!("{path}.providers.pyth"),
+                format!("`{feed_id}` is not a 32 bytes hex feed id"),
+            );
+        }
+    }
+
+    if let Some(symbol) = &asset.providers.binance {
+        if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            problems.push(
+                format!("{path}.providers.binance"),
+                format!("`{symbol}` is not a Binance symbol like ETHUSDT"),
+            );
+        }
+    }
+
+    if let Some(product_id) = &asset.providers.coinbase {
+        let is_product_id = product_id
+            .split_once('-')
+            .is_some_and(|(base, quote)| !base.is_empty() && !quote.is_empty());
+
+        if !is_product_id {
+            problems.push(
+                format!("{path}.providers.coinbase"),
+                format!("`{product_id}` is not a Coinbase product id like ETH-USD"),
+            );
+        }
+    }
+
+    for name in asset.providers.http.keys() {
+        if !http_names.contains_key(name.as_str()) {
+            problems.push(
+                format!("{path}.providers.http.{name}"),
+                format!("no [[providers.http]] named `{name}`"),
+            );
+        }
+    }
+
+    if let Some(MockPrice::RandomWalk { volatility, .. }) = &asset.providers.mock {
+        if !(0.0..1.0).contains(volatility) {
+            problems.push(
+                format!("{path}.providers.mock.volatility"),
+                "must be at least 0 and less than 1",
+            );
+        }
+    }
+}
+
+fn validate_retry(problems: &mut Problems, path: &str, policy: &RetryPolicy) {
+    if policy.max_attempts == 0 {
+        problems.push(format!("{path}.max_attempts"), "must be at least 1");
+    }
+
+    if policy.initial_backoff_ms > policy.max_backoff_ms {
+        problems.push(
+            format!("{path}.initial_backoff_ms"),
+            "must not be greater than max_backoff_ms",
+        );
+    }
+
+    if !(0.0..1.0).contains(&policy.jitter) {
+        problems.push(
+            format!("{path}.jitter"),
+            "must be at least 0 and less than 1",
+        );
+    }
+}
+
+/// A list of providers must name known providers, once each
+fn validate_provider_names(
+    problems: &mut Problems,
+    path: &str,
+    priority: &[String],
+    is_provider: impl Fn(&str) -> bool,
+) {
+    for (index, name) in priority.iter().enumerate() {
+        if !is_provider(name) {
+            problems.push(
+                format!("{path}[{index}]"),
+                format!("unknown provider `{name}`"),
+            );
+        } else if priority[..index].contains(name) {
+            problems.push(
+                format!("{path}[{index}]"),
+                format!("`{name}` is listed twice"),
+            );
+        }
+    }
+}
+
+/// The quote asset of a pool must be another asset declared on the same chain
+fn validate_quote(
+    problems: &mut Problems,
+    config: &ConfigServiceInner,
+    asset: &Asset,
+    path: &str,
+    quote: &str,
+) {
+    let quote_id = AssetId::new(asset.chain.clone(), quote).ok();
+    let has_quote = config
+        .assets
+        .iter()
+        .any(|other| Some(other.id()) == quote_id);
+
+    if quote_id == Some(asset.id()) || !has_quote {
+        problems.push(
+            format!("{path}.quote"),
+            format!(
+                "`{quote}` must be the address of another asset declared on {}",
+                asset.chain
+            ),
+        );
+    }
+}
+
+/// On-chain sources need an EVM asset chain with an `[[rpc]]` url once their provider is enabled
+fn validate_evm_rpc(
+    problems: &mut Problems,
+    rpc_chains: &HashMap<u64, usize>,
+    asset: &Asset,
+    path: &str,
+    enabled: bool,
+    source: &str,
+) {
+    match asset.chain {
+        Chain::Evm(chain_id) => {
+            if enabled && !rpc_chains.contains_key(&chain_id) {
+                problems.push(
+                    path,
+                    format!("no [[rpc]] url configured for chain {chain_id}"),
+                );
+            }
+        }
+        _ => problems.push(path, format!("{source} are only supported on EVM chains")),
+    }
+}
+
+fn validate_url(problems: &mut Problems, path: &str, value: &str) {
+    validate_url_scheme(problems, path, value, &["http", "https"]);
+}
+
+fn validate_url_scheme(problems: &mut Problems, path: &str, value: &str, schemes: &[&str]) {
+    match Url::parse(value) {
+        Ok(url) if schemes.contains(&url.scheme()) => {}
+        Ok(url) => problems.push(
+            path,
+            format!(
+                "`{value}` must use {}, got `{}`",
+                schemes.join(" or "),
+                url.scheme()
+            ),
+        ),
+        Err(e) => problems.push(path, format!("`{value}` is not a valid URL: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigService;
+
+    const ENVIRONMENT: &str = r#"
+        [environment]
+        name = "test"
+        otlp_grpc_endpoint = "http://localhost:4317"
+        otlp_http_endpoint = "http://localhost:4318"
+    "#;
+
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    /// Paths and messages of the problems of a config, in the order they were found
+    fn problems(config: &str) -> Vec<(String, String)> {
+        let config = format!("[tasks.fetcher]\ninterval = 10\n{ENVIRONMENT}\n{config}");
+
+        match config.parse::<ConfigService>() {
+            Ok(_) => Vec::new(),
+            Err(report) => {
+                // Frames are iterated from the last attachment
+                let mut problems = report
+                    .frames()
+                    .filter_map(|frame| frame.downcast_ref::<ConfigProblem>())
+                    .map(|problem| (problem.path.clone(), problem.message.clone()))
+                    .collect::<Vec<_>>();
+                problems.reverse();
+                problems
+            }
+        }
+    }
+
+    fn asset(address: &str, chain: &str) -> String {
+        format!(
+            r#"
+            [[assets]]
+            address = "{address}"
+            symbol = "TOKEN"
+            chain = "{chain}"
+            decimals = 18
+            "#
+        )
+    }
+
+    fn paths(problems: Vec<(String, String)>) -> Vec<String> {
+        problems.into_iter().map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn valid_config_has_no_problem() {
+        let config = [
+            asset(WETH, "evm:1"),
+            asset("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "svm:1"),
+        ]
+        .concat();
+
+        assert_eq!(problems(&config), Vec::new());
+    }
+
+    #[test]
+    fn zero_fetch_interval_is_rejected() {
+        let config = format!("[tasks.fetcher]\ninterval = 0\n{ENVIRONMENT}")
+            .parse::<ConfigService>()
+            .unwrap_err();
+
+        let problems = config
+            .frames()
+            .filter_map(|frame| frame.downcast_ref::<ConfigProblem>())
+            .map(|problem| problem.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(problems, vec!["tasks.fetcher.interval"]);
+    }
+
+    #[test]
+    fn bad_urls_are_rejected() {
+        let config = r#"
+            [providers.coingecko]
+            base_url = "not a url"
+
+            [providers.binance]
+            base_url = "https://stream.binance.com:9443"
+
+            [[rpc]]
+            chain_id = 1
+            url = "ftp://localhost:8545"
+        "#;
+
+        let problems = problems(config);
+        assert_eq!(
+            paths(problems.clone()),
+            vec![
+                "providers.coingecko.base_url",
+                "providers.binance.base_url",
+                "rpc[0].url"
+            ]
+        );
+        assert!(problems[1].1.contains("must use ws or wss, got `https`"));
+    }
+
+    #[test]
+    fn mixed_case_http_provider_names_are_rejected() {
+        let config = r#"
+            [[providers.http]]
+            name = "MyApi"
+            url = "https://api.example.com/price/{id}"
+            price_pointer = "/price"
+
+            [rate_limits.MyApi]
+            requests_per_second = 1.0
+        "#;
+
+        // The name is still known, so the rate limit is not reported as well
+        let problems = problems(config);
+        assert_eq!(paths(problems.clone()), vec!["providers.http[0].name"]);
+        assert!(problems[0].1.contains("`MyApi` must be lowercase"));
+    }
+
+    #[test]
+    fn jupiter_batches_are_limited_to_100_mints() {
+        let config = |batch_size: usize| {
+            format!("[providers.jupiter]\nbase_url = \"https://lite-api.jup.ag\"\nbatch_size = {batch_size}")
+        };
+
+        assert_eq!(problems(&config(100)), Vec::new());
+        assert_eq!(
+            paths(problems(&config(101))),
+            vec!["providers.jupiter.batch_size"]
+        );
+    }
+
+    #[test]
+    fn duplicate_addresses_are_rejected() {
+        let config = [asset(WETH, "evm:1"), asset(&WETH.to_lowercase(), "evm:1")].concat();
+
+        let problems = problems(&config);
+        assert_eq!(paths(problems.clone()), vec!["assets[1].address"]);
+        assert!(problems[0].1.starts_with("duplicates assets[0]"));
+
+        // The same address on another chain is another asset
+        let config = [asset(WETH, "evm:1"), asset(WETH, "evm:10")].concat();
+        assert_eq!(self::problems(&config), Vec::new());
+    }
+
+    #[test]
+    fn bad_checksums_and_encodings_are_rejected() {
+        let bad_checksum = WETH.replace("aaA", "aaa");
+        let config = [
+            asset(&bad_checksum, "evm:1"),
+            asset("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756C", "evm:1"),
+            asset("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt10", "svm:1"),
+            asset("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGk", "svm:1"),
+        ]
+        .concat();
+
+        let problems = problems(&config);
+        let messages = problems
+            .iter()
+            .map(|(_, message)| message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(problems.len(), 4);
+        assert!(messages[0].contains(&format!("invalid EIP-55 checksum, expected `{WETH}`")));
+        assert!(messages[1].contains("must have 40 hexadecimal characters"));
+        assert!(messages[2].contains("is not valid base58"));
+        assert!(messages[3].contains("must decode to 32 bytes"));
+    }
+
+    #[test]
+    fn volatility_and_jitter_must_be_below_1() {
+        let with_volatility = |volatility: f64| {
+            format!(
+                r#"
+                [tasks.fetcher.retry]
+                jitter = {volatility}
+
+                [[assets]]
+                address = "{WETH}"
+                symbol = "WETH"
+                chain = "evm:1"
+                decimals = 18
+                providers.mock = {{ kind = "random_walk", start = "2000", volatility = {volatility} }}
+                "#
+            )
+        };
+
+        assert_eq!(problems(&with_volatility(0.0)), Vec::new());
+        assert_eq!(problems(&with_volatility(0.5)), Vec::new());
+
+        let message = String::from("must be at least 0 and less than 1");
+        assert_eq!(
+            problems(&with_volatility(1.0)),
+            vec![
+                (String::from("tasks.fetcher.retry.jitter"), message.clone()),
+                (String::from("assets[0].providers.mock.volatility"), message),
+            ]
+        );
+    }
+
+    #[test]
+    fn rate_limits_of_providers_without_requests_are_rejected() {
+        let config = r#"
+            [rate_limits.binance]
+            requests_per_second = 1
+
+            [rate_limits.mock]
+            requests_per_second = 1
+
+            [rate_limits.chainlink]
+            requests_per_second = 1
+        "#;
+
+        let mut paths = paths(problems(config));
+        paths.sort();
+        assert_eq!(paths, vec!["rate_limits.binance", "rate_limits.mock"]);
+    }
+
+    #[test]
+    fn aggregation_ages_must_be_positive() {
+        let config = r#"
+            [aggregation]
+            failover_after = 0
+
+            [aggregation.quorum]
+            min_providers = 1
+            tolerance = 0.01
+            max_age = 0
+        "#;
+
+        assert_eq!(
+            paths(problems(config)),
+            vec!["aggregation.failover_after", "aggregation.quorum.max_age"]
+        );
+        assert_eq!(problems(&config.replace("= 0\n", "= 30\n")), Vec::new());
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use error_stack::{Report, Result};
+use lib::error::Error;
+use std::{
+    fs,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{error, info, info_span, Instrument};
+
+use super::{loader::ConfigLoader, ConfigService};
+use crate::services::{ServiceFactory, ServiceProvider};
+
+/// How often the config file is checked for modifications
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Observable handle over the config file.
+///
+/// Holds the latest successfully parsed `ConfigService` and notifies subscribers
+/// every time the file on disk changes. Reloads go through the same `ConfigLoader`
+/// as the initial load, so environment and CLI overrides keep applying. Invalid
+/// files are logged and ignored, so subscribers always see a valid config.
+#[derive(Clone)]
+pub struct ConfigWatcher {
+    inner: Arc<ConfigWatcherInner>,
+}
+
+struct ConfigWatcherInner {
+    loader: ConfigLoader,
+    services: ServiceProvider,
+    sender: watch::Sender<ConfigService>,
+}
+
+impl ConfigWatcher {
+    pub fn new(services: ServiceProvider, loader: ConfigLoader, config: ConfigService) -> Self {
+        let (sender, _) = watch::channel(config);
+
+        Self {
+            inner: Arc::new(ConfigWatcherInner {
+                loader,
+                services,
+                sender,
+            }),
+        }
+    }
+
+    /// Latest valid config
+    pub fn current(&self) -> ConfigService {
+        self.inner.sender.borrow().clone()
+    }
+
+    /// Subscribe to config changes
+    pub fn subscribe(&self) -> watch::Receiver<ConfigService> {
+        self.inner.sender.subscribe()
+    }
+
+    /// Reload the config and publish it if it is valid
+    pub async fn reload(&self) -> Result<ConfigService, Error> {
+        let config = self.inner.loader.load()?;
+
+        // Keep the cached service in sync for consumers resolving it lazily
+        self.inner.services.add_service(config.clone()).await;
+        self.inner.sender.send_replace(config.clone());
+
+        Ok(config)
+    }
+
+    /// Start watching the config file for modifications
+    pub fn start(&self) -> JoinHandle<Result<(), Error>> {
+        let watcher = self.clone();
+        let path = self
+            .inner
+            .loader
+            .path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let span = info_span!("config_watcher", path).or_current();
+
+        tokio::spawn(watcher.watch().instrument(span))
+    }
+
+    async fn watch(self) -> Result<(), Error> {
+        if self.inner.loader.path().is_none() {
+            info!("No config file to watch");
+            return Ok(());
+        }
+
+        let mut last_modified = self.modified_at();
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let modified = self.modified_at();
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match self.reload().await {
+                Ok(_) => info!("Reloaded config file"),
+                Err(e) => error!("Failed to reload config file, keeping previous config: {e:?}"),
+            }
+        }
+    }
+
+    fn modified_at(&self) -> Option<SystemTime> {
+        let path = self.inner.loader.path()?;
+
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+#[async_trait]
+impl ServiceFactory for ConfigWatcher {
+    async fn factory(_services: ServiceProvider) -> Result<Self, Error> {
+        Err(Report::new(Error::Unknown).attach_printable(
+            "Config watcher needs the config loader. You must initialize it manually.",
+        ))
+    }
+}
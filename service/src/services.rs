@@ -110,8 +110,8 @@ impl ServiceProvider {
     {
         self.get_service::<T>()
             .await
-            .expect(format!("Failed to initialize service: {}", type_name::<T>()).as_str())
-            .expect(format!("Failed to get service: {}", type_name::<T>()).as_str())
+            .unwrap_or_else(|_| panic!("Failed to initialize service: {}", type_name::<T>()))
+            .unwrap_or_else(|| panic!("Failed to get service: {}", type_name::<T>()))
     }
 
     /// Warm up the service by initializing it
@@ -13,16 +13,13 @@ use tracing_subscriber::{registry::LookupSpan, Layer};
 
 use super::{get_otlp_resource, TelemetryParams};
 
-pub fn new<S, R>(
-    telemetry_params: TelemetryParams,
-) -> Result<
-    (
-        Box<dyn Layer<S> + Send + Sync>, // stdout layer
-        Box<dyn Layer<R> + Send + Sync>, // otel layer
-        LoggerProvider,
-    ),
-    Error,
->
+type LogLayers<S, R> = (
+    Box<dyn Layer<S> + Send + Sync>, // stdout layer
+    Box<dyn Layer<R> + Send + Sync>, // otel layer
+    LoggerProvider,
+);
+
+pub fn new<S, R>(telemetry_params: TelemetryParams) -> Result<LogLayers<S, R>, Error>
 where
     S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
     R: Subscriber + for<'a> LookupSpan<'a> + Send + Sync + 'static,
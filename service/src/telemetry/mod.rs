@@ -148,10 +148,11 @@ fn init_panic_hook() {
 ///
 /// # Example
 /// ```rust
+/// # use service::telemetry::get_otlp_resource;
 /// let resource_indexer = get_otlp_resource("indexer", "stage");
 /// let resource_graphql = get_otlp_resource("graphql", "stage");
 /// ```
-pub(crate) fn get_otlp_resource<'a>(service_name: &'a str, service_namespace: &'a str) -> Resource {
+pub fn get_otlp_resource<'a>(service_name: &'a str, service_namespace: &'a str) -> Resource {
     // Use OpenTelemetry's semantic conventions for standard attribute keys.
     let service_attr = KeyValue::new("service.name", service_name.to_owned());
     let service_namespace_attr = KeyValue::new("service.namespace", service_namespace.to_owned());
@@ -0,0 +1,96 @@
+mod common;
+
+use async_tungstenite::tungstenite::Message;
+use chrono::{TimeZone, Utc};
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::{AssetPriceProvider, PriceProvider};
+use service::asset::price::providers::binance::BinanceStreamProvider;
+use service::config::providers::{BinanceConfig, BinanceStream};
+use std::time::Duration;
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+    providers.binance = "ETHUSDT"
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+"#;
+
+fn mini_ticker(close: &str, event_time: i64) -> Message {
+    Message::Text(
+        serde_json::json!({
+            "stream": "ethusdt@miniTicker",
+            "data": { "e": "24hrMiniTicker", "E": event_time, "s": "ETHUSDT", "c": close }
+        })
+        .to_string(),
+    )
+}
+
+#[tokio::test]
+async fn streams_throttled_ticks_and_reconnects() {
+    let server = common::WsServer::start().await;
+    let services = common::services(ASSETS).await;
+
+    let provider = BinanceStreamProvider::new(
+        services.clone(),
+        BinanceConfig {
+            base_url: server.url.clone(),
+            stream: BinanceStream::MiniTicker,
+            throttle_ms: 60_000,
+        },
+    )
+    .await;
+    for asset in common::assets(&services).await {
+        provider.add_asset(asset).await.unwrap();
+    }
+
+    let mut events = provider.subscribe();
+    provider.start();
+
+    // Only assets with a Binance symbol are subscribed to
+    let (uri, mut connection) = server.accept().await;
+    assert_eq!(uri, "/stream?streams=ethusdt@miniTicker");
+
+    connection
+        .send(mini_ticker("2000.5", 1_700_000_000_000))
+        .await
+        .unwrap();
+    // Within the throttle delay of the previous tick
+    connection
+        .send(mini_ticker("2001", 1_700_000_000_500))
+        .await
+        .unwrap();
+
+    let event = events.next().await.unwrap();
+    assert_eq!(event.provider, AssetPriceProvider::Binance);
+    assert_eq!(event.asset.symbol, "WETH");
+    assert_eq!(event.price, Decimal::new(20005, 1));
+    assert_eq!(
+        event.fetched_at,
+        Utc.timestamp_millis_opt(1_700_000_000_000).unwrap()
+    );
+
+    // Dropping the socket makes the provider reconnect and subscribe again
+    drop(connection);
+    let (uri, mut connection) = server.accept().await;
+    assert_eq!(uri, "/stream?streams=ethusdt@miniTicker");
+
+    connection
+        .send(mini_ticker("2002", 1_700_000_002_000))
+        .await
+        .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.price, Decimal::from(2002));
+}
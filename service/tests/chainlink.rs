@@ -0,0 +1,110 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Bytes, TransactionRequest};
+use ethers::utils::Anvil;
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::PriceProvider;
+use service::asset::price::providers::chainlink::ChainlinkProvider;
+use service::asset::price::providers::polling::PollingProvider;
+use service::config::providers::ChainlinkConfig;
+use std::str::FromStr;
+
+const DECIMALS: u8 = 8;
+const ANSWER: u64 = 200_012_345_678;
+const UPDATED_AT: u32 = 1_700_000_000;
+
+/// Creation code of an aggregator answering `decimals()` and `latestRoundData()`
+/// with constants, and reverting on any other call
+fn mock_aggregator(decimals: u8, answer: u64, updated_at: u32) -> Bytes {
+    let mut runtime = vec![
+        0x60, 0x00, 0x35, // CALLDATALOAD(0)
+        0x60, 0xe0, 0x1c, // SHR(224): selector
+        0x80, 0x63, 0x31, 0x3c, 0xe5, 0x67, 0x14, // DUP1 EQ(decimals())
+        0x60, 0x1d, 0x57, // JUMPI(0x1d)
+        0x63, 0xfe, 0xaf, 0x96, 0x8c, 0x14, // EQ(latestRoundData())
+        0x60, 0x28, 0x57, // JUMPI(0x28)
+        0x60, 0x00, 0x80, 0xfd, // REVERT(0, 0)
+        // 0x1d: return decimals
+        0x5b, 0x60, decimals, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+        // 0x28: return (1, answer, updated_at, updated_at, 1)
+        0x5b, 0x60, 0x01, 0x60, 0x00, 0x52, 0x67,
+    ];
+    runtime.extend(answer.to_be_bytes());
+    runtime.extend([0x60, 0x20, 0x52, 0x63]);
+    runtime.extend(updated_at.to_be_bytes());
+    runtime.extend([0x60, 0x40, 0x52, 0x63]);
+    runtime.extend(updated_at.to_be_bytes());
+    runtime.extend([
+        0x60, 0x60, 0x52, 0x60, 0x01, 0x60, 0x80, 0x52, // store answeredInRound
+        0x60, 0xa0, 0x60, 0x00, 0xf3, // RETURN(0, 160)
+    ]);
+
+    // Copy the runtime code after this 11 bytes prefix to memory and return it
+    let length = runtime.len() as u8;
+    let mut creation = vec![
+        0x60, length, 0x80, // PUSH1 length, DUP1
+        0x60, 0x0b, 0x60, 0x00, 0x39, // CODECOPY(0, 0x0b, length)
+        0x60, 0x00, 0xf3, // RETURN(0, length)
+    ];
+    creation.extend(runtime);
+
+    creation.into()
+}
+
+#[tokio::test]
+#[ignore = "requires anvil on PATH, run with --ignored"]
+async fn reads_the_latest_round_of_an_aggregator() {
+    let anvil = Anvil::new().spawn();
+    let client = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+
+    let deployment = TransactionRequest::new()
+        .from(anvil.addresses()[0])
+        .data(mock_aggregator(DECIMALS, ANSWER, UPDATED_AT));
+    let aggregator = client
+        .send_transaction(deployment, None)
+        .await
+        .unwrap()
+        .await
+        .unwrap()
+        .and_then(|receipt| receipt.contract_address)
+        .expect("aggregator deployed");
+
+    let services = common::services(&format!(
+        r#"
+        [providers.chainlink]
+
+        [[rpc]]
+        chain_id = {chain_id}
+        url = "{endpoint}"
+
+        [[assets]]
+        address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+        symbol = "WETH"
+        chain = "evm:{chain_id}"
+        decimals = 18
+        providers.chainlink = "{aggregator:?}"
+        "#,
+        chain_id = anvil.chain_id(),
+        endpoint = anvil.endpoint(),
+    ))
+    .await;
+
+    let provider = ChainlinkProvider::new(services.clone(), ChainlinkConfig::default())
+        .await
+        .unwrap();
+    for asset in common::assets(&services).await {
+        provider.add_asset(asset).await.unwrap();
+    }
+
+    let events = provider.fetch().await.unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].price, Decimal::from_str("2000.12345678").unwrap());
+    assert_eq!(
+        events[0].fetched_at,
+        Utc.timestamp_opt(UPDATED_AT.into(), 0).unwrap()
+    );
+    assert_eq!(events[0].metadata.exponent, Some(-8));
+}
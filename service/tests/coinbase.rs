@@ -0,0 +1,121 @@
+mod common;
+
+use async_tungstenite::tungstenite::Message;
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use service::asset::price::price_provider::PriceProvider;
+use service::asset::price::providers::coinbase::CoinbaseStreamProvider;
+use service::config::providers::CoinbaseConfig;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+    providers.coinbase = "ETH-USD"
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+"#;
+
+fn ticker(sequence_num: u64, price: &str) -> Message {
+    Message::Text(
+        json!({
+            "channel": "ticker",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "sequence_num": sequence_num,
+            "events": [{ "type": "update", "tickers": [{ "product_id": "ETH-USD", "price": price }] }]
+        })
+        .to_string(),
+    )
+}
+
+fn heartbeat(sequence_num: u64) -> Message {
+    Message::Text(
+        json!({
+            "channel": "heartbeats",
+            "timestamp": "2024-01-01T00:00:01Z",
+            "sequence_num": sequence_num,
+            "events": [{ "current_time": "2024-01-01T00:00:01Z", "heartbeat_counter": 1 }]
+        })
+        .to_string(),
+    )
+}
+
+#[tokio::test]
+async fn fetches_a_snapshot_on_sequence_gaps() {
+    let server = common::WsServer::start().await;
+    let rest = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/brokerage/market/products/ETH-USD"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "product_id": "ETH-USD", "price": "2005" })),
+        )
+        .expect(1)
+        .mount(&rest)
+        .await;
+
+    let services = common::services(ASSETS).await;
+    let provider = CoinbaseStreamProvider::new(
+        services.clone(),
+        CoinbaseConfig {
+            ws_url: server.url.clone(),
+            rest_url: rest.uri(),
+        },
+    )
+    .await
+    .unwrap();
+    for asset in common::assets(&services).await {
+        provider.add_asset(asset).await.unwrap();
+    }
+
+    let mut events = provider.subscribe();
+    provider.start();
+
+    let (_, mut connection) = server.accept().await;
+
+    // Only products of tracked assets are subscribed to, on both channels
+    for channel in ["ticker", "heartbeats"] {
+        let Some(Ok(Message::Text(text))) = connection.next().await else {
+            panic!("expected a {channel} subscription");
+        };
+        let subscription = serde_json::from_str::<Value>(&text).unwrap();
+        assert_eq!(
+            subscription,
+            json!({ "type": "subscribe", "channel": channel, "product_ids": ["ETH-USD"] })
+        );
+    }
+
+    for message in [
+        ticker(1, "2000"),
+        ticker(2, "2001"),
+        heartbeat(3),
+        // Messages 4 to 6 were missed
+        ticker(7, "2010"),
+    ] {
+        connection.send(message).await.unwrap();
+    }
+
+    let mut prices = Vec::new();
+    for _ in 0..4 {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        prices.push(event.price);
+    }
+
+    assert_eq!(prices, [2000, 2001, 2005, 2010].map(Decimal::from).to_vec());
+
+    // Only the gap triggered a snapshot
+    rest.verify().await;
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use service::asset::price::providers::coingecko::CoinGeckoProvider;
+use service::asset::price::providers::polling::PollingProvider;
+use service::config::providers::CoinGeckoConfig;
+use std::str::FromStr;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+
+    [[assets]]
+    address = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"
+    symbol = "BTC"
+    chain = "bitcoin:mainnet"
+    decimals = 8
+"#;
+
+/// USDT on TON, configured by its user-friendly address
+const TON_USDT: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+
+const TON_ASSETS: &str = r#"
+    [[assets]]
+    address = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs"
+    symbol = "USDT"
+    chain = "ton:mainnet"
+    decimals = 6
+"#;
+
+/// Provider requesting one asset at a time from `server`
+async fn provider(server: &MockServer) -> CoinGeckoProvider {
+    provider_of(server, ASSETS).await
+}
+
+/// Provider of `assets` requesting one asset at a time from `server`
+async fn provider_of(server: &MockServer, assets: &str) -> CoinGeckoProvider {
+    let services = common::services(assets).await;
+    let config = CoinGeckoConfig {
+        base_url: server.uri(),
+        api_key: Some(String::from("demo-key")),
+        batch_size: 1,
+        ..Default::default()
+    };
+
+    let provider = CoinGeckoProvider::new(services.clone(), config)
+        .await
+        .unwrap();
+    common::track_assets(&provider, &services).await;
+
+    provider
+}
+
+fn token_price(address: &str, usd: f64) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        address: { "usd": usd, "last_updated_at": 1_700_000_000 }
+    }))
+}
+
+fn token_price_request(address: &str) -> wiremock::MockBuilder {
+    Mock::given(method("GET"))
+        .and(path("/simple/token_price/ethereum"))
+        .and(query_param("contract_addresses", address))
+        .and(query_param("vs_currencies", "usd"))
+        .and(header("x-cg-demo-api-key", "demo-key"))
+}
+
+#[tokio::test]
+async fn prices_the_assets_of_the_batches_that_succeed() {
+    let server = MockServer::start().await;
+    token_price_request(WETH)
+        .respond_with(token_price(WETH, 2000.5))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = provider(&server).await;
+    let events =
+        common::fetch_with_failing_batch(&provider, &server, token_price_request(USDC)).await;
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].asset.symbol, "WETH");
+    assert_eq!(events[0].price, Decimal::from_str("2000.5").unwrap());
+    assert_eq!(
+        events[0].fetched_at,
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn fails_when_every_batch_fails() {
+    let server = MockServer::start().await;
+    common::assert_fails_when_every_batch_fails(&provider(&server).await, &server).await;
+}
+
+#[tokio::test]
+async fn skips_chains_without_a_platform() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+        .mount(&server)
+        .await;
+
+    provider(&server).await.fetch().await.unwrap();
+
+    // Only the two ethereum assets are requested, bitcoin has no platform
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|request| request.url.path() == "/simple/token_price/ethereum"));
+}
+
+#[tokio::test]
+async fn requests_ton_tokens_by_user_friendly_address() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/simple/token_price/the-open-network"))
+        .and(query_param("contract_addresses", TON_USDT))
+        .respond_with(token_price(TON_USDT, 0.9995))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let events = provider_of(&server, TON_ASSETS)
+        .await
+        .fetch()
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].asset.symbol, "USDT");
+    assert_eq!(events[0].price, Decimal::from_str("0.9995").unwrap());
+}
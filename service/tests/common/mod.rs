@@ -0,0 +1,135 @@
+use service::asset::price::price_provider::{AssetPriceEvent, PriceProvider};
+use service::asset::price::providers::polling::PollingProvider;
+use service::{config::ConfigService, services::ServiceProvider};
+use wiremock::{Mock, MockBuilder, MockServer, ResponseTemplate};
+
+/// Services holding the config made of `config` and a test environment
+pub async fn services(config: &str) -> ServiceProvider {
+    let config = format!(
+        r#"
+        [tasks.fetcher]
+        interval = 1
+
+        [environment]
+        name = "test"
+        otlp_grpc_endpoint = "http://localhost:4317"
+        otlp_http_endpoint = "http://localhost:4318"
+
+        [providers.defillama]
+        enabled = false
+
+        {config}
+        "#
+    )
+    .parse::<ConfigService>()
+    .unwrap();
+
+    let services = ServiceProvider::new();
+    services.add_service(config).await;
+    services
+}
+
+/// Assets of the config held by `services`
+#[allow(dead_code)]
+pub async fn assets(services: &ServiceProvider) -> Vec<service::asset::Asset> {
+    services
+        .get_service_unchecked::<ConfigService>()
+        .await
+        .assets
+        .clone()
+}
+
+/// Add the assets of the config held by `services` to `provider`
+#[allow(dead_code)]
+pub async fn track_assets(provider: &impl PriceProvider, services: &ServiceProvider) {
+    for asset in assets(services).await {
+        provider.add_asset(asset).await.unwrap();
+    }
+}
+
+/// Fetch from `provider` while the one request matching `failing` answers
+/// with a 500, which must not fail the batches of the other requests
+#[allow(dead_code)]
+pub async fn fetch_with_failing_batch(
+    provider: &impl PollingProvider,
+    server: &MockServer,
+    failing: MockBuilder,
+) -> Vec<AssetPriceEvent> {
+    failing
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(server)
+        .await;
+
+    provider
+        .fetch()
+        .await
+        .expect("a failing batch failed the whole fetch")
+}
+
+/// Assert that `provider` fails once every request to `server` fails
+#[allow(dead_code)]
+pub async fn assert_fails_when_every_batch_fails(
+    provider: &impl PollingProvider,
+    server: &MockServer,
+) {
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(500))
+        .mount(server)
+        .await;
+
+    assert!(provider.fetch().await.is_err());
+}
+
+/// Connection accepted by a `WsServer`
+#[allow(dead_code)]
+pub type WsConnection = async_tungstenite::WebSocketStream<
+    async_tungstenite::tokio::TokioAdapter<tokio::net::TcpStream>,
+>;
+
+/// Local WebSocket server accepting connections one at a time
+#[allow(dead_code)]
+pub struct WsServer {
+    pub url: String,
+    listener: tokio::net::TcpListener,
+}
+
+#[allow(dead_code)]
+impl WsServer {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        Self { url, listener }
+    }
+
+    /// Accept the next connection, returning it along with its request path
+    /// and query. Connections closed during the handshake are skipped, as
+    /// providers drop them when their assets change while connecting.
+    #[allow(clippy::result_large_err)]
+    pub async fn accept(&self) -> (String, WsConnection) {
+        let accept = async {
+            loop {
+                let (stream, _) = self.listener.accept().await.unwrap();
+                let mut uri = String::new();
+                let connection = async_tungstenite::tokio::accept_hdr_async(
+                    stream,
+                    |request: &async_tungstenite::tungstenite::handshake::server::Request,
+                     response| {
+                        uri = request.uri().to_string();
+                        Ok(response)
+                    },
+                )
+                .await;
+
+                if let Ok(connection) = connection {
+                    return (uri, connection);
+                }
+            }
+        };
+
+        tokio::time::timeout(std::time::Duration::from_secs(10), accept)
+            .await
+            .expect("no connection within 10s")
+    }
+}
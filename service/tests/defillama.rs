@@ -0,0 +1,191 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::AssetPriceProvider;
+use service::asset::price::providers::defillama::DefiLlamaProvider;
+use service::asset::price::providers::polling::PollingProvider;
+use service::config::providers::DefiLlamaConfig;
+use std::str::FromStr;
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+const WBTC: &str = "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599";
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+
+    [[assets]]
+    address = "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599"
+    symbol = "WBTC"
+    chain = "evm:1"
+    decimals = 8
+"#;
+
+/// USDT on TON, configured by its user-friendly address
+const TON_USDT: &str = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs";
+
+const TON_ASSETS: &str = r#"
+    [[assets]]
+    address = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs"
+    symbol = "USDT"
+    chain = "ton:mainnet"
+    decimals = 6
+"#;
+
+/// Provider requesting one coin at a time from `server`
+async fn provider(server: &MockServer) -> DefiLlamaProvider {
+    provider_of(server, ASSETS).await
+}
+
+/// Provider of `assets` requesting one asset at a time from `server`
+async fn provider_of(server: &MockServer, assets: &str) -> DefiLlamaProvider {
+    let services = common::services(assets).await;
+    let config = DefiLlamaConfig {
+        base_url: server.uri(),
+        batch_size: 1,
+        ..Default::default()
+    };
+
+    let provider = DefiLlamaProvider::new(services.clone(), config).await;
+    common::track_assets(&provider, &services).await;
+
+    provider
+}
+
+/// Request of the coin of `address` on ethereum, whatever the case of the address
+fn coin_request(address: &str) -> wiremock::MockBuilder {
+    Mock::given(method("GET")).and(path_regex(format!(
+        "(?i)^/prices/current/ethereum:{address}$"
+    )))
+}
+
+/// Price of the coin of `address` on ethereum
+fn coin_price(address: &str, price: f64) -> ResponseTemplate {
+    chain_coin_price("ethereum", address, price)
+}
+
+/// Price of the coin of `address` on the chain of `slug`
+fn chain_coin_price(slug: &str, address: &str, price: f64) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "coins": {
+            format!("{slug}:{address}"): {
+                "decimals": 18,
+                "symbol": "TOKEN",
+                "price": price,
+                "timestamp": 1_700_000_000,
+                "confidence": 0.99
+            }
+        }
+    }))
+}
+
+#[tokio::test]
+async fn prices_the_assets_of_the_chunks_that_succeed() {
+    let server = MockServer::start().await;
+    coin_request(WETH)
+        .respond_with(coin_price(WETH, 2000.5))
+        .expect(1)
+        .mount(&server)
+        .await;
+    coin_request(WBTC)
+        .respond_with(coin_price(WBTC, 40000.0))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = provider(&server).await;
+    let mut events = common::fetch_with_failing_batch(&provider, &server, coin_request(USDC)).await;
+    events.sort_by(|a, b| a.asset.symbol.cmp(&b.asset.symbol));
+
+    // USDC is missing, its chunk failed
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| event.provider == AssetPriceProvider::DeFiLlama));
+    assert_eq!(events[0].asset.symbol, "WBTC");
+    assert_eq!(events[0].price, Decimal::from(40_000));
+    assert_eq!(events[1].asset.symbol, "WETH");
+    assert_eq!(events[1].price, Decimal::from_str("2000.5").unwrap());
+    assert_eq!(
+        events[1].fetched_at,
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn fails_when_every_chunk_fails() {
+    let server = MockServer::start().await;
+    common::assert_fails_when_every_batch_fails(&provider(&server).await, &server).await;
+}
+
+#[tokio::test]
+async fn requests_ton_tokens_by_user_friendly_address() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/prices/current/ton:{TON_USDT}")))
+        .respond_with(chain_coin_price("ton", TON_USDT, 0.9995))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let events = provider_of(&server, TON_ASSETS)
+        .await
+        .fetch()
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].asset.symbol, "USDT");
+    assert_eq!(events[0].price, Decimal::from_str("0.9995").unwrap());
+}
+
+#[tokio::test]
+async fn skips_coins_with_an_invalid_price_or_timestamp() {
+    let server = MockServer::start().await;
+    let coin = |address: &str, info: serde_json::Value| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "coins": { format!("ethereum:{address}"): info }
+        }))
+    };
+    // Only the price and timestamp are needed
+    coin_request(WETH)
+        .respond_with(coin(
+            WETH,
+            serde_json::json!({ "price": 2000.5, "timestamp": 1_700_000_000 }),
+        ))
+        .mount(&server)
+        .await;
+    coin_request(USDC)
+        .respond_with(coin(
+            USDC,
+            serde_json::json!({ "price": 0.0, "timestamp": 1_700_000_000 }),
+        ))
+        .mount(&server)
+        .await;
+    coin_request(WBTC)
+        .respond_with(coin(
+            WBTC,
+            serde_json::json!({ "price": 40000.0, "timestamp": i64::MAX }),
+        ))
+        .mount(&server)
+        .await;
+
+    let events = provider(&server).await.fetch().await.unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].asset.symbol, "WETH");
+    assert_eq!(events[0].price, Decimal::from_str("2000.5").unwrap());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::AssetPriceProvider;
+use service::asset::price::providers::http::GenericHttpProvider;
+use service::asset::price::providers::polling::PollingProvider;
+use service::config::ConfigService;
+use std::str::FromStr;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+    providers.http.internal = "weth&eth"
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+    providers.http.internal = "usdc"
+"#;
+
+/// `internal` instance requesting one asset at a time from `server`
+async fn provider(server: &MockServer) -> GenericHttpProvider {
+    let services = common::services(&format!(
+        r#"
+        [[providers.http]]
+        name = "internal"
+        url = "{uri}/prices?ids={{ids}}"
+        price_pointer = "/{{id}}/price"
+
+        {ASSETS}
+        "#,
+        uri = server.uri(),
+    ))
+    .await;
+    let config = services
+        .get_service_unchecked::<ConfigService>()
+        .await
+        .providers
+        .http[0]
+        .clone();
+
+    let provider = GenericHttpProvider::new(services.clone(), config)
+        .await
+        .unwrap();
+    common::track_assets(&provider, &services).await;
+
+    provider
+}
+
+/// Request of the `internal` instance for the ids of a batch
+fn prices_request(ids: &str) -> wiremock::MockBuilder {
+    Mock::given(method("GET"))
+        .and(path("/prices"))
+        .and(query_param("ids", ids))
+}
+
+#[tokio::test]
+async fn prices_the_assets_of_the_batches_that_succeed() {
+    let server = MockServer::start().await;
+    // The id is only a single query value when encoded
+    prices_request("weth&eth")
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "weth&eth": { "price": "2000.5" }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = provider(&server).await;
+    let events = common::fetch_with_failing_batch(&provider, &server, prices_request("usdc")).await;
+
+    assert_eq!(provider.name(), "internal");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].asset.symbol, "WETH");
+    assert_eq!(
+        events[0].provider,
+        AssetPriceProvider::Http(String::from("internal"))
+    );
+    assert_eq!(events[0].price, Decimal::from_str("2000.5").unwrap());
+}
+
+#[tokio::test]
+async fn fails_when_every_batch_fails() {
+    let server = MockServer::start().await;
+    common::assert_fails_when_every_batch_fails(&provider(&server).await, &server).await;
+}
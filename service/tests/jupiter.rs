@@ -0,0 +1,125 @@
+mod common;
+
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::AssetPriceProvider;
+use service::asset::price::providers::jupiter::JupiterProvider;
+use service::asset::price::providers::polling::PollingProvider;
+use service::config::providers::JupiterConfig;
+use std::collections::HashSet;
+use std::str::FromStr;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const JUP: &str = "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN";
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+    symbol = "USDC"
+    chain = "svm:1"
+    decimals = 6
+
+    [[assets]]
+    address = "So11111111111111111111111111111111111111112"
+    symbol = "SOL"
+    chain = "svm:1"
+    decimals = 9
+
+    [[assets]]
+    address = "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN"
+    symbol = "JUP"
+    chain = "svm:1"
+    decimals = 6
+
+    [[assets]]
+    address = "4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU"
+    symbol = "USDC"
+    chain = "svm:2"
+    decimals = 6
+
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+"#;
+
+/// Provider requesting two mints at a time from `server`
+async fn provider(server: &MockServer) -> JupiterProvider {
+    let services = common::services(ASSETS).await;
+    let config = JupiterConfig {
+        base_url: server.uri(),
+        api_key: Some(String::from("jup-key")),
+        batch_size: 2,
+    };
+
+    let provider = JupiterProvider::new(services.clone(), config)
+        .await
+        .unwrap();
+    common::track_assets(&provider, &services).await;
+
+    provider
+}
+
+#[tokio::test]
+async fn prices_solana_mints_in_batches() {
+    let server = MockServer::start().await;
+    // Every batch gets the whole answer, only the requested mints are priced
+    Mock::given(method("GET"))
+        .and(path("/price/v2"))
+        .and(header("x-api-key", "jup-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "data": {
+                USDC: { "id": USDC, "type": "derivedPrice", "price": "0.9998" },
+                SOL: { "id": SOL, "type": "derivedPrice", "price": "1.5e2" },
+                JUP: null
+            },
+            "timeTaken": 0.002
+        })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let mut events = provider(&server).await.fetch().await.unwrap();
+    events.sort_by(|a, b| a.asset.symbol.cmp(&b.asset.symbol));
+
+    // JUP has no price, devnet USDC and WETH are not on Solana mainnet
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| event.provider == AssetPriceProvider::Jupiter));
+    assert_eq!(events[0].asset.symbol, "SOL");
+    assert_eq!(events[0].price, Decimal::from(150));
+    assert_eq!(events[1].asset.symbol, "USDC");
+    assert_eq!(events[1].price, Decimal::from_str("0.9998").unwrap());
+
+    // The three mainnet mints are requested once each, two at most per request
+    let requests = server.received_requests().await.unwrap();
+    let batches = requests
+        .iter()
+        .map(|request| {
+            let (_, ids) = request
+                .url
+                .query_pairs()
+                .find(|(name, _)| name == "ids")
+                .unwrap();
+            ids.split(',').map(String::from).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    assert!(batches.iter().all(|batch| batch.len() <= 2));
+    let mints = batches.into_iter().flatten().collect::<Vec<_>>();
+    assert_eq!(mints.len(), 3);
+    assert_eq!(
+        mints.into_iter().collect::<HashSet<_>>(),
+        HashSet::from([USDC, SOL, JUP].map(String::from))
+    );
+}
+
+#[tokio::test]
+async fn fails_when_every_batch_fails() {
+    let server = MockServer::start().await;
+    common::assert_fails_when_every_batch_fails(&provider(&server).await, &server).await;
+}
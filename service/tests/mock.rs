@@ -0,0 +1,131 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use futures::StreamExt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use service::asset::price::aggregator::AggregatedPriceEvent;
+use service::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider};
+use service::asset::price::PriceService;
+use std::io::Write;
+use std::pin::Pin;
+use tokio_stream::Stream;
+
+const SEED: u64 = 42;
+const VOLATILITY: f64 = 0.01;
+
+/// Started price service of the mock provider seeded with `SEED`, pricing
+/// WETH from `mock`, along with its price and aggregated price streams
+async fn start(
+    mock: &str,
+) -> (
+    Pin<Box<dyn Stream<Item = AssetPriceEvent> + Send>>,
+    Pin<Box<dyn Stream<Item = AggregatedPriceEvent> + Send>>,
+) {
+    let services = common::services(&format!(
+        r#"
+        [providers.mock]
+        seed = {SEED}
+
+        [[assets]]
+        address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+        symbol = "WETH"
+        chain = "evm:1"
+        decimals = 18
+        providers.mock = {mock}
+        "#
+    ))
+    .await;
+
+    let mut price_service = PriceService::new(services.clone()).await;
+    for asset in common::assets(&services).await {
+        price_service.add_asset(asset).await;
+    }
+
+    let events = price_service.subscribe().await;
+    let aggregated = price_service.subscribe_aggregated();
+    price_service.start().await;
+
+    (events, aggregated)
+}
+
+async fn next_prices<T>(
+    stream: &mut Pin<Box<dyn Stream<Item = T> + Send>>,
+    count: usize,
+    price: impl Fn(&T) -> Decimal,
+) -> Vec<Decimal> {
+    stream.take(count).map(|item| price(&item)).collect().await
+}
+
+#[tokio::test(start_paused = true)]
+async fn publishes_the_prices_of_a_script_in_order() {
+    let mut script = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+    writeln!(script, "timestamp,price").unwrap();
+    writeln!(script, "1700000000,2000").unwrap();
+    writeln!(script, "1700000060,2010.5").unwrap();
+    writeln!(script, "1700000120,1995.25").unwrap();
+
+    let (mut events, mut aggregated) = start(&format!(
+        r#"{{ kind = "script", path = "{}" }}"#,
+        script.path().display()
+    ))
+    .await;
+
+    let events = events.by_ref().take(3).collect::<Vec<_>>().await;
+    let expected = [
+        (1_700_000_000, "2000"),
+        (1_700_000_060, "2010.5"),
+        (1_700_000_120, "1995.25"),
+    ]
+    .map(|(timestamp, price)| {
+        (
+            Utc.timestamp_opt(timestamp, 0).unwrap(),
+            price.parse().unwrap(),
+        )
+    });
+
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| (event.fetched_at, event.price))
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert!(events
+        .iter()
+        .all(|event| event.provider == AssetPriceProvider::Mock));
+
+    assert_eq!(
+        next_prices(&mut aggregated, 3, |event| event.price).await,
+        expected.map(|(_, price)| price)
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn publishes_the_random_walk_of_the_seed() {
+    let start_price = Decimal::from(3000);
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let expected = (0..5)
+        .scan(start_price, |price, _| {
+            let step = Decimal::from_f64(rng.gen_range(-VOLATILITY..=VOLATILITY)).unwrap();
+            *price *= Decimal::ONE + step;
+            Some(*price)
+        })
+        .collect::<Vec<_>>();
+
+    let (mut events, mut aggregated) = start(&format!(
+        r#"{{ kind = "random_walk", start = "{start_price}", volatility = {VOLATILITY} }}"#
+    ))
+    .await;
+
+    assert_eq!(
+        next_prices(&mut events, 5, |event| event.price).await,
+        expected
+    );
+    assert_eq!(
+        next_prices(&mut aggregated, 5, |event| event.price).await,
+        expected
+    );
+}
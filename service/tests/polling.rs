@@ -0,0 +1,72 @@
+mod common;
+
+use chrono::Utc;
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::PriceProvider;
+use service::asset::price::providers::coingecko::CoinGeckoProvider;
+use service::asset::price::providers::health::HealthState;
+use service::config::providers::CoinGeckoConfig;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+"#;
+
+#[tokio::test]
+async fn keeps_publishing_after_a_failed_tick() {
+    let server = MockServer::start().await;
+    // The first fetch fails without being retried, the next tick succeeds
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(400))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/simple/token_price/ethereum"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            WETH: { "usd": 2000.0, "last_updated_at": Utc::now().timestamp() }
+        })))
+        .mount(&server)
+        .await;
+
+    let services = common::services(ASSETS).await;
+    let config = CoinGeckoConfig {
+        base_url: server.uri(),
+        ..Default::default()
+    };
+    let provider = CoinGeckoProvider::new(services.clone(), config)
+        .await
+        .unwrap();
+    for asset in common::assets(&services).await {
+        provider.add_asset(asset).await.unwrap();
+    }
+
+    let mut events = provider.subscribe();
+    let task = provider.start();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), events.next())
+        .await
+        .expect("no price event within 10s")
+        .unwrap();
+    task.abort();
+
+    assert_eq!(event.asset.symbol, "WETH");
+    assert_eq!(event.price, Decimal::from(2000));
+
+    // One failed tick, then a successful one
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    let health = provider.health();
+    assert_eq!(health.state, HealthState::Healthy);
+    assert_eq!(health.consecutive_failures, 0);
+    assert!(health.last_failure.is_some());
+}
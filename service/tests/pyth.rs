@@ -0,0 +1,176 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceProvider};
+use service::asset::price::providers::polling::PollingProvider;
+use service::asset::price::providers::pyth::PythProvider;
+use service::config::providers::{PythConfig, PythMode};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ETH_USD: &str = "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace";
+
+const ASSETS: &str = r#"
+    [[assets]]
+    address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    symbol = "WETH"
+    chain = "evm:1"
+    decimals = 18
+    providers.pyth = "0xFF61491A931112DDF1BD8147CD1B641375F79F5825126D665480874634FD0ACE"
+
+    [[assets]]
+    address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    symbol = "USDC"
+    chain = "evm:1"
+    decimals = 6
+"#;
+
+async fn provider(server: &MockServer, mode: PythMode) -> PythProvider {
+    provider_with(PythConfig {
+        base_url: server.uri(),
+        mode,
+        ..Default::default()
+    })
+    .await
+}
+
+async fn provider_with(config: PythConfig) -> PythProvider {
+    let services = common::services(ASSETS).await;
+
+    let provider = PythProvider::new(services.clone(), config).await.unwrap();
+    common::track_assets(&provider, &services).await;
+
+    provider
+}
+
+/// Hermes price update of the ETH/USD feed at 2000.5 ± 1.5
+fn price_update() -> serde_json::Value {
+    serde_json::json!({
+        "binary": { "encoding": "hex", "data": [] },
+        "parsed": [{
+            "id": ETH_USD,
+            "price": {
+                "price": "200050000000",
+                "conf": "150000000",
+                "expo": -8,
+                "publish_time": 1_700_000_000
+            },
+            "ema_price": {
+                "price": "200000000000",
+                "conf": "100000000",
+                "expo": -8,
+                "publish_time": 1_700_000_000
+            }
+        }]
+    })
+}
+
+fn assert_eth_price(event: &AssetPriceEvent) {
+    assert_eq!(event.provider, AssetPriceProvider::Pyth);
+    assert_eq!(event.asset.symbol, "WETH");
+    assert_eq!(event.price, Decimal::from_str("2000.5").unwrap());
+    assert_eq!(
+        event.fetched_at,
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    );
+    assert_eq!(
+        event.metadata.confidence,
+        Some(Decimal::from_str("1.5").unwrap())
+    );
+    assert_eq!(event.metadata.exponent, Some(-8));
+}
+
+#[tokio::test]
+async fn polls_the_latest_prices_of_the_feeds() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/updates/price/latest"))
+        .and(query_param("ids[]", format!("0x{ETH_USD}")))
+        .and(query_param("parsed", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(price_update()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let events = provider(&server, PythMode::Poll)
+        .await
+        .fetch()
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eth_price(&events[0]);
+}
+
+#[tokio::test]
+async fn streams_server_sent_events_with_crlf_line_endings() {
+    let server = MockServer::start().await;
+    let body = format!(
+        ":keepalive\r\n\r\nid: 1\r\ndata: {}\r\n\r\n",
+        price_update()
+    );
+    Mock::given(method("GET"))
+        .and(path("/v2/updates/price/stream"))
+        .and(query_param("ids[]", format!("0x{ETH_USD}")))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let provider = provider(&server, PythMode::Stream).await;
+    let mut events = provider.subscribe();
+    provider.start();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), events.next())
+        .await
+        .expect("no price event within 10s")
+        .unwrap();
+    assert_eth_price(&event);
+}
+
+#[tokio::test]
+async fn reconnects_a_stalled_stream() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    // Every connection receives one price update, then stalls without closing
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\ndata: {}\n\n",
+                price_update()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            connections.push(socket);
+        }
+    });
+
+    let provider = provider_with(PythConfig {
+        base_url,
+        mode: PythMode::Stream,
+        stream_idle_secs: 1,
+    })
+    .await;
+    let mut events = provider.subscribe();
+    provider.start();
+
+    // The second update is only sent once the stalled connection was replaced
+    for _ in 0..2 {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.next())
+            .await
+            .expect("no price event within 10s")
+            .unwrap();
+        assert_eth_price(&event);
+    }
+}
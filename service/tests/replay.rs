@@ -0,0 +1,99 @@
+mod common;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use service::asset::price::price_provider::{AssetPriceEvent, AssetPriceProvider, PriceMetadata};
+use service::asset::price::recorder::RecordedEvent;
+use service::asset::price::PriceService;
+use std::io::Write;
+use tokio::time::Instant;
+
+const RECORDED_AT: i64 = 1_700_000_000;
+
+/// Line recorded `offset` seconds into the recording, of a price fetched
+/// `age` seconds before
+fn line(
+    asset: &service::asset::Asset,
+    provider: AssetPriceProvider,
+    offset: i64,
+    age: i64,
+) -> String {
+    let recorded_at: DateTime<Utc> = Utc.timestamp_opt(RECORDED_AT + offset, 0).unwrap();
+    let recorded = RecordedEvent {
+        recorded_at,
+        event: AssetPriceEvent {
+            provider,
+            asset: asset.clone(),
+            price: Decimal::from(2000 + offset),
+            fetched_at: recorded_at - Duration::seconds(age),
+            metadata: PriceMetadata::default(),
+        },
+    };
+
+    serde_json::to_string(&recorded).unwrap()
+}
+
+#[tokio::test(start_paused = true)]
+async fn replays_at_the_recorded_pace_without_recording_again() {
+    let mut recording = tempfile::Builder::new()
+        .suffix(".jsonl")
+        .tempfile()
+        .unwrap();
+    let output = tempfile::NamedTempFile::new().unwrap();
+
+    let services = common::services(&format!(
+        r#"
+        [tasks.recorder]
+        path = "{output}"
+
+        [providers.replay]
+        path = "{recording}"
+        shift_timestamps = false
+
+        [[assets]]
+        address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+        symbol = "WETH"
+        chain = "evm:1"
+        decimals = 18
+        "#,
+        output = output.path().display(),
+        recording = recording.path().display(),
+    ))
+    .await;
+    let weth = common::assets(&services).await.remove(0);
+
+    // The Chainlink price is older, but was received after the Pyth one
+    writeln!(recording, "{}", line(&weth, AssetPriceProvider::Pyth, 0, 1)).unwrap();
+    writeln!(
+        recording,
+        "{}",
+        line(&weth, AssetPriceProvider::Chainlink, 10, 30)
+    )
+    .unwrap();
+
+    let mut price_service = PriceService::new(services.clone()).await;
+    price_service.add_asset(weth).await;
+
+    let mut events = price_service.subscribe().await;
+    let started_at = Instant::now();
+    price_service.start().await;
+
+    let first = events.next().await.unwrap();
+    assert_eq!(first.provider, AssetPriceProvider::Pyth);
+    assert_eq!(
+        first.fetched_at,
+        Utc.timestamp_opt(RECORDED_AT - 1, 0).unwrap()
+    );
+
+    let second = events.next().await.unwrap();
+    assert_eq!(second.provider, AssetPriceProvider::Chainlink);
+    assert_eq!(
+        second.fetched_at,
+        Utc.timestamp_opt(RECORDED_AT - 20, 0).unwrap()
+    );
+    assert!(started_at.elapsed() >= std::time::Duration::from_secs(10));
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(std::fs::read_to_string(output.path()).unwrap(), "");
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[rustfmt::skip]
 #[derive(Parser)]
@@ -11,11 +11,17 @@ use clap::{Parser, ValueEnum};
 pub struct Cli {
     #[clap(
         long,
-        required = false,
-        default_value = "config.toml",
-        help = "Path to the configuration file"
+        env = "CONFIG_PATH",
+        help = "Path to the configuration file [default: config.toml, if present]"
     )]
-    pub config: String,
+    pub config: Option<String>,
+    #[clap(
+        long = "set",
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Override a config field, e.g. `--set tasks.fetcher.interval=5`. Takes precedence over SHOGUN__* environment variables"
+    )]
+    pub overrides: Vec<(String, String)>,
     #[clap(
         long, 
         value_enum,
@@ -23,6 +29,24 @@ pub struct Cli {
         help = "Log level"
     )]
     pub log_level: LogLevel,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands run instead of the price fetcher
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Load and validate the configuration, reporting every problem found. Exits non-zero if invalid.
+    ValidateConfig,
+}
+
+/// Parse a `key=value` config override
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `KEY=VALUE`, got `{s}`"))?;
+
+    Ok((key.trim().to_string(), value.to_string()))
 }
 
 /// Log levels which allow to specify the verbosity of the logs output.
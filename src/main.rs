@@ -1,25 +1,36 @@
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command};
 use futures::StreamExt;
 use service::{
-    asset::{price::PriceService, Asset, Chain},
-    config::ConfigService,
+    asset::price::PriceService,
+    config::{loader::ConfigLoader, watcher::ConfigWatcher},
     services::ServiceProvider,
     telemetry,
 };
-use std::path::Path;
-use tracing::info;
+use std::{path::PathBuf, sync::Arc};
+use tracing::{info, warn};
 
 mod cli;
 
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
 #[tokio::main]
 #[tracing::instrument]
 async fn main() {
     info!("Starting grafana shogun");
     let args = Cli::parse();
 
-    let config =
-        ConfigService::read_file(Path::new(&args.config)).expect("Failed to read config file");
+    let config_loader = ConfigLoader::builder()
+        .and_path(config_path(args.config))
+        .env(std::env::vars().collect::<Vec<_>>())
+        .cli_overrides(args.overrides)
+        .build();
+
+    if let Some(Command::ValidateConfig) = args.command {
+        validate_config(&config_loader);
+    }
+
+    let config = config_loader.load().expect("Failed to load config");
 
     let service_name = String::from("shogun");
 
@@ -31,40 +42,63 @@ async fn main() {
     let services = ServiceProvider::new();
     services.add_service(config.clone()).await;
 
+    let config_watcher = services
+        .add_service(ConfigWatcher::new(
+            services.clone(),
+            config_loader,
+            config.clone(),
+        ))
+        .await;
+
     let mut price_service = PriceService::new(services).await;
 
-    // hardcoded assets to fetch ))
-    let trump_solana_address = "6p6xgHyF7AeE6TZkSmFsko444wqoP15icUSqi2jfGiPN";
-    let weth_ethreum_address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    if config.assets.is_empty() {
+        warn!("No assets declared in config, nothing will be fetched");
+    }
 
-    let trump_coin = Asset::builder()
-        .address(trump_solana_address.to_string())
-        .symbol("TRUMP".to_string())
-        .chain(Chain::Svm(1))
-        .name(String::from("Trump Coin"))
-        .decimals(6)
-        .build();
+    for asset in config.assets.iter().cloned() {
+        price_service.add_asset(asset).await;
+    }
 
-    let weth_coin = Asset::builder()
-        .address(weth_ethreum_address.to_string())
-        .symbol("WETH".to_string())
-        .chain(Chain::Evm(1))
-        .name(String::from("Wrapped Ether"))
-        .decimals(18)
-        .build();
+    price_service.start().await;
 
-    price_service.add_asset(weth_coin).await;
-    price_service.add_asset(trump_coin).await;
+    let price_service = Arc::new(price_service);
 
-    price_service.start().await;
+    config_watcher.start();
+    price_service
+        .clone()
+        .watch_config(config_watcher.subscribe());
 
-    let mut stream_handler = price_service.subscribe().await;
+    let mut stream_handler = price_service.subscribe_aggregated();
 
     while let Some(event) = stream_handler.next().await {
-        info!("Received a new asset price event: {:?}", event);
+        info!("Received a new aggregated asset price: {:?}", event);
     }
 
     telemetry::shutdown()
         .await
         .expect("Failed to shutdown telemetry");
 }
+
+/// Config file to load: the one given through `--config`/`CONFIG_PATH`, or
+/// `config.toml` when present. Without one, config comes from overrides only.
+fn config_path(path: Option<String>) -> Option<PathBuf> {
+    match path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Some(PathBuf::from(DEFAULT_CONFIG_PATH)).filter(|path| path.exists()),
+    }
+}
+
+/// Validate the config and exit, with a non-zero code if it is invalid
+fn validate_config(config_loader: &ConfigLoader) -> ! {
+    match config_loader.load() {
+        Ok(_) => {
+            println!("Config is valid");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Config is invalid: {e:?}");
+            std::process::exit(1);
+        }
+    }
+}